@@ -64,6 +64,8 @@ impl Plugin for AssetConfigPlugin {
                 _app,
                 "assets/environment_maps/pisa_specular_rgb9e5_zstd.ktx2"
             );
+            embedded_asset!(_app, "assets/palettes/sunset.png");
+            embedded_asset!(_app, "assets/palettes/ocean.png");
         }
     }
 }
@@ -0,0 +1,396 @@
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit};
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+
+use crate::gamepad::gamepad_orbit;
+use crate::gesture::TouchGestures;
+use crate::toggle::FeatureToggles;
+use crate::{CameraRotation, CameraRotationEnabled};
+
+/// Radians of camera orbit per logical pixel dragged
+const DRAG_ORBIT_SPEED: f32 = 0.005;
+/// Camera height change per logical pixel dragged
+const DRAG_HEIGHT_SPEED: f32 = 0.01;
+/// How much one scroll wheel line zooms, pixel scrolling is scaled to match
+const SCROLL_LINE_ZOOM: f32 = 0.1;
+const SCROLL_PIXEL_ZOOM: f32 = 0.002;
+/// Closest/furthest the camera can zoom
+const MIN_CAMERA_RADIUS: f32 = 1.5;
+const MAX_CAMERA_RADIUS: f32 = 15.0;
+/// Lowest/highest the camera can be dragged
+const MIN_CAMERA_HEIGHT: f32 = -3.0;
+const MAX_CAMERA_HEIGHT: f32 = 8.0;
+/// Below this the inertia is considered stopped
+const STOPPED: f32 = 1e-4;
+
+/// Marker component for camera rotation
+#[derive(Component)]
+pub struct RotatingCamera {
+    /// Rotation speed in radians per second (positive = clockwise when viewed from above)
+    pub speed: f32,
+    /// Radius of rotation around the center
+    pub radius: f32,
+    /// Center to rotate around
+    pub center: Vec3,
+    /// Current angle in radians
+    pub angle: f32,
+    /// Height of the camera
+    pub height: f32,
+}
+
+impl RotatingCamera {
+    /// Orbit around center that passes through position
+    pub fn new(position: Vec3, center: Vec3, speed: f32) -> Self {
+        let offset = position - center;
+        Self {
+            speed,
+            radius: offset.xz().length(),
+            center,
+            angle: offset.z.atan2(offset.x),
+            height: position.y,
+        }
+    }
+
+    /// Transform on the orbit looking at the center
+    pub fn transform(&self) -> Transform {
+        let x = self.center.x + self.radius * self.angle.cos();
+        let z = self.center.z + self.radius * self.angle.sin();
+
+        Transform::from_xyz(x, self.height, z).looking_at(self.center, Vec3::Y)
+    }
+}
+
+/// User orbit input for one frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrbitInput {
+    /// Logical pixels dragged
+    pub drag: Vec2,
+    /// Radius multiplier, below 1 zooms in
+    pub zoom: f32,
+}
+
+impl Default for OrbitInput {
+    fn default() -> Self {
+        Self {
+            drag: Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl OrbitInput {
+    fn is_active(&self) -> bool {
+        self.drag != Vec2::ZERO || self.zoom != 1.0
+    }
+}
+
+/// How user orbiting feels and when auto rotation comes back
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct OrbitSettings {
+    /// Seconds without input before auto rotation resumes
+    pub idle_secs: f32,
+    /// How quickly a flick slows down, higher stops sooner
+    pub damping: f32,
+    /// Seconds auto rotation takes to get back up to full speed
+    pub resume_secs: f32,
+}
+
+impl Default for OrbitSettings {
+    fn default() -> Self {
+        Self {
+            idle_secs: 3.0,
+            damping: 4.0,
+            resume_secs: 1.5,
+        }
+    }
+}
+
+/// User orbit state that lives next to RotatingCamera
+#[derive(Component, Debug)]
+pub struct OrbitControl {
+    /// Angle and height change per second left over from the last drag
+    velocity: Vec2,
+    /// Log radius change per second left over from the last zoom
+    zoom_velocity: f32,
+    /// Seconds since the last input
+    idle: f32,
+    /// 0..1 how much of the auto rotation speed applies
+    auto: f32,
+}
+
+impl Default for OrbitControl {
+    fn default() -> Self {
+        Self {
+            velocity: Vec2::ZERO,
+            zoom_velocity: 0.0,
+            idle: 0.0,
+            auto: 1.0,
+        }
+    }
+}
+
+impl OrbitControl {
+    /// Eased 0..1 share of the auto rotation speed
+    pub fn auto_rotation(&self) -> f32 {
+        let t = self.auto.clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    fn is_moving(&self) -> bool {
+        self.velocity != Vec2::ZERO || self.zoom_velocity != 0.0
+    }
+
+    /// Apply input or coast on inertia, returns true if the camera moved
+    pub fn step(
+        &mut self,
+        camera: &mut RotatingCamera,
+        input: OrbitInput,
+        dt: f32,
+        settings: &OrbitSettings,
+    ) -> bool {
+        if dt <= 0.0 {
+            return false;
+        }
+
+        if input.is_active() {
+            // Follow the pointer exactly, whatever speed it had carries on
+            // after letting go
+            self.velocity = Vec2::new(
+                input.drag.x * DRAG_ORBIT_SPEED,
+                input.drag.y * DRAG_HEIGHT_SPEED,
+            ) / dt;
+            self.zoom_velocity = input.zoom.ln() / dt;
+            self.idle = 0.0;
+            self.auto = 0.0;
+        } else {
+            let decay = (-settings.damping * dt).exp();
+            self.velocity *= decay;
+            self.zoom_velocity *= decay;
+            if self.velocity.length() < STOPPED {
+                self.velocity = Vec2::ZERO;
+            }
+            if self.zoom_velocity.abs() < STOPPED {
+                self.zoom_velocity = 0.0;
+            }
+
+            self.idle += dt;
+            if self.idle >= settings.idle_secs && self.auto < 1.0 {
+                self.auto = (self.auto + dt / settings.resume_secs.max(f32::EPSILON)).min(1.0);
+            }
+        }
+
+        if !self.is_moving() {
+            return false;
+        }
+
+        camera.angle += self.velocity.x * dt;
+        camera.height =
+            (camera.height + self.velocity.y * dt).clamp(MIN_CAMERA_HEIGHT, MAX_CAMERA_HEIGHT);
+        camera.radius = (camera.radius * (self.zoom_velocity * dt).exp())
+            .clamp(MIN_CAMERA_RADIUS, MAX_CAMERA_RADIUS);
+        true
+    }
+}
+
+/// Plugin for the auto rotating camera and user orbit control on top of it
+pub struct OrbitCameraPlugin;
+
+impl Plugin for OrbitCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OrbitSettings>().add_systems(
+            Update,
+            (
+                orbit_camera,
+                rotate_camera.run_if(any_with_component::<CameraRotationEnabled>),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Mouse drag/scroll plus touch drag/pinch and gamepad sticks, anything over
+/// egui is egui's
+fn gather_input(
+    mouse_buttons: &ButtonInput<MouseButton>,
+    motion: &AccumulatedMouseMotion,
+    scroll: &AccumulatedMouseScroll,
+    gestures: &TouchGestures,
+    sticks: OrbitInput,
+    egui_wants_pointer: bool,
+) -> OrbitInput {
+    // Touch gestures already ignore sequences that start on egui
+    let mut input = OrbitInput {
+        drag: gestures.drag + sticks.drag,
+        zoom: sticks.zoom / gestures.pinch,
+    };
+
+    if egui_wants_pointer {
+        return input;
+    }
+
+    if mouse_buttons.pressed(MouseButton::Left) {
+        input.drag += motion.delta;
+    }
+
+    let lines = match scroll.unit {
+        MouseScrollUnit::Line => scroll.delta.y * SCROLL_LINE_ZOOM,
+        MouseScrollUnit::Pixel => scroll.delta.y * SCROLL_PIXEL_ZOOM,
+    };
+    // Scrolling up zooms in
+    input.zoom *= (-lines).exp();
+
+    input
+}
+
+/// User driven orbit with inertia, on real time so it still works while the
+/// scene is paused or slowed down
+#[allow(clippy::too_many_arguments)]
+fn orbit_camera(
+    time: Res<Time<Real>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    scroll: Res<AccumulatedMouseScroll>,
+    gestures: Res<TouchGestures>,
+    gamepads: Query<&Gamepad>,
+    egui_input: Option<Res<EguiWantsInput>>,
+    settings: Res<OrbitSettings>,
+    mut query: Query<(&mut Transform, &mut RotatingCamera, &mut OrbitControl)>,
+) {
+    let egui_wants_pointer = egui_input.is_some_and(|e| e.wants_any_pointer_input());
+    let input = gather_input(
+        &mouse_buttons,
+        &motion,
+        &scroll,
+        &gestures,
+        gamepad_orbit(gamepads.iter(), time.delta_secs()),
+        egui_wants_pointer,
+    );
+
+    for (mut transform, mut camera, mut control) in query.iter_mut() {
+        if control.step(&mut camera, input, time.delta_secs(), &settings) {
+            *transform = camera.transform();
+        }
+    }
+}
+
+/// Rotation of the camera around the origin/center point, eases back in after
+/// the user lets go and in/out when toggled
+fn rotate_camera(
+    time: Res<Time>,
+    toggles: Res<FeatureToggles>,
+    mut query: Query<
+        (&mut Transform, &mut RotatingCamera, Option<&OrbitControl>),
+        With<CameraRotationEnabled>,
+    >,
+) {
+    let intensity = toggles.intensity::<CameraRotation>();
+
+    for (mut transform, mut camera, control) in query.iter_mut() {
+        let scale = control.map_or(1.0, OrbitControl::auto_rotation) * intensity;
+        if scale <= 0.0 {
+            continue;
+        }
+
+        camera.angle += camera.speed * scale * time.delta_secs();
+        *transform = camera.transform();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera() -> RotatingCamera {
+        RotatingCamera::new(Vec3::new(3.0, 1.0, 3.0), Vec3::new(0.0, -0.5, 0.0), 0.3)
+    }
+
+    #[test]
+    fn test_new_round_trips_position() {
+        let position = Vec3::new(3.0, 1.0, 3.0);
+        let camera = camera();
+        assert!(camera.transform().translation.distance(position) < 1e-4);
+    }
+
+    #[test]
+    fn test_drag_then_inertia_decays() {
+        let settings = OrbitSettings::default();
+        let mut camera = camera();
+        let mut control = OrbitControl::default();
+        let start = camera.angle;
+
+        let drag = OrbitInput {
+            drag: Vec2::new(100.0, 0.0),
+            ..default()
+        };
+        assert!(control.step(&mut camera, drag, 0.1, &settings));
+        assert!((camera.angle - start - 100.0 * DRAG_ORBIT_SPEED).abs() < 1e-4);
+        assert_eq!(control.auto_rotation(), 0.0);
+
+        // Keeps coasting after letting go, but less each frame until it stops
+        let before = camera.angle;
+        assert!(control.step(&mut camera, OrbitInput::default(), 0.1, &settings));
+        let coast = camera.angle - before;
+        assert!(coast > 0.0 && coast < 100.0 * DRAG_ORBIT_SPEED);
+
+        for _ in 0..200 {
+            control.step(&mut camera, OrbitInput::default(), 0.1, &settings);
+        }
+        assert!(!control.is_moving());
+    }
+
+    #[test]
+    fn test_zoom_is_clamped() {
+        let settings = OrbitSettings::default();
+        let mut camera = camera();
+        let mut control = OrbitControl::default();
+
+        let zoom_in = OrbitInput {
+            zoom: 0.01,
+            ..default()
+        };
+        control.step(&mut camera, zoom_in, 0.016, &settings);
+        assert_eq!(camera.radius, MIN_CAMERA_RADIUS);
+
+        let zoom_out = OrbitInput {
+            zoom: 100.0,
+            ..default()
+        };
+        control.step(&mut camera, zoom_out, 0.016, &settings);
+        assert_eq!(camera.radius, MAX_CAMERA_RADIUS);
+    }
+
+    #[test]
+    fn test_auto_rotation_resumes_after_idle() {
+        let settings = OrbitSettings {
+            idle_secs: 1.0,
+            resume_secs: 1.0,
+            ..default()
+        };
+        let mut camera = camera();
+        let mut control = OrbitControl::default();
+
+        let drag = OrbitInput {
+            drag: Vec2::new(10.0, 0.0),
+            ..default()
+        };
+        control.step(&mut camera, drag, 0.1, &settings);
+
+        // Still idling, nothing yet
+        for _ in 0..9 {
+            control.step(&mut camera, OrbitInput::default(), 0.1, &settings);
+        }
+        assert_eq!(control.auto_rotation(), 0.0);
+
+        // Part way back up to speed
+        for _ in 0..6 {
+            control.step(&mut camera, OrbitInput::default(), 0.1, &settings);
+        }
+        let partial = control.auto_rotation();
+        assert!(partial > 0.0 && partial < 1.0);
+
+        for _ in 0..10 {
+            control.step(&mut camera, OrbitInput::default(), 0.1, &settings);
+        }
+        assert_eq!(control.auto_rotation(), 1.0);
+    }
+}
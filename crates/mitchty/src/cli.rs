@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+pub const USAGE: &str = "usage: mitchty [--seed N] [--fixed-timestep SECS]
+
+  --seed N               seed for everything random, same seed same scene
+  --fixed-timestep SECS  advance time by exactly SECS every frame, with a seed
+                         this makes runs frame for frame reproducible";
+
+/// Native command line options
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CliArgs {
+    pub seed: Option<u64>,
+    pub fixed_timestep: Option<f64>,
+    pub help: bool,
+    /// Arguments that aren't ours, launchers like macOS's add their own (-psn_*)
+    pub ignored: Vec<String>,
+}
+
+/// Parse arguments, not including the program name
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<CliArgs, String> {
+    let mut parsed = CliArgs::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        // Take --flag=value as well as --flag value
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{flag} needs a value"))
+        };
+
+        match flag.as_str() {
+            "--seed" => {
+                let text = value()?;
+                parsed.seed = Some(text.parse().map_err(|_| format!("bad seed {text}"))?);
+            }
+            "--fixed-timestep" => {
+                let text = value()?;
+                let secs: f64 = text
+                    .parse()
+                    .map_err(|_| format!("bad fixed timestep {text}"))?;
+                if secs <= 0.0 || !secs.is_finite() {
+                    return Err(format!("fixed timestep must be positive, got {text}"));
+                }
+                parsed.fixed_timestep = Some(secs);
+            }
+            "-h" | "--help" => parsed.help = true,
+            _ => parsed.ignored.push(arg),
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Plugin applying command line options main parsed, --help and bad
+/// arguments never get this far
+pub struct CliPlugin(pub CliArgs);
+
+impl Plugin for CliPlugin {
+    fn build(&self, app: &mut App) {
+        for arg in &self.0.ignored {
+            warn!("ignoring unknown argument {arg}");
+        }
+        if let Some(seed) = self.0.seed {
+            app.insert_resource(crate::SceneSeed(seed));
+        }
+        if let Some(secs) = self.0.fixed_timestep {
+            app.insert_resource(bevy::time::TimeUpdateStrategy::ManualDuration(
+                std::time::Duration::from_secs_f64(secs),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, String> {
+        parse_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse(&[]), Ok(CliArgs::default()));
+        assert_eq!(
+            parse(&["--seed", "42", "--fixed-timestep=0.016"]),
+            Ok(CliArgs {
+                seed: Some(42),
+                fixed_timestep: Some(0.016),
+                help: false,
+                ignored: Vec::new(),
+            })
+        );
+        assert_eq!(parse(&["--seed=7"]).unwrap().seed, Some(7));
+    }
+
+    #[test]
+    fn test_parse_args_ignores_unknown() {
+        let args = parse(&["-psn_0_12345", "--seed", "3", "--nope=1"]).unwrap();
+        assert_eq!(args.seed, Some(3));
+        assert_eq!(args.ignored, vec!["-psn_0_12345", "--nope=1"]);
+    }
+
+    #[test]
+    fn test_plugin_applies_args() {
+        let mut app = App::new();
+        app.add_plugins(CliPlugin(
+            parse(&["--seed", "5", "--fixed-timestep", "0.5"]).unwrap(),
+        ));
+
+        assert_eq!(app.world().resource::<crate::SceneSeed>().0, 5);
+        assert!(matches!(
+            app.world().resource::<bevy::time::TimeUpdateStrategy>(),
+            bevy::time::TimeUpdateStrategy::ManualDuration(d) if d.as_secs_f64() == 0.5
+        ));
+    }
+
+    #[test]
+    fn test_parse_args_errors() {
+        assert!(parse(&["--seed"]).is_err());
+        assert!(parse(&["--seed", "x"]).is_err());
+        assert!(parse(&["--fixed-timestep", "0"]).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use bevy::prelude::*;
+
+use crate::input::{Binding, InputActions, register_action};
+
+/// Speeds the slower/faster actions step through
+pub const SPEEDS: [f32; 6] = [0.1, 0.25, 0.5, 1.0, 2.0, 4.0];
+
+/// Where a single step is at
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Step {
+    #[default]
+    Idle,
+    /// Unpaused, waiting on the next frame's time update
+    Requested,
+    /// This frame is the step, pause again
+    Running,
+}
+
+/// How fast the scene runs. Everything animated goes by Time<Virtual> so this
+/// pauses/slows the cubes, camera and hue together, input and ui fades stay on
+/// real time.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct TimeControl {
+    /// Multiplier on real time
+    pub speed: f32,
+    pub paused: bool,
+    step: Step,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            paused: false,
+            step: Step::Idle,
+        }
+    }
+}
+
+impl TimeControl {
+    /// Next speed down in SPEEDS
+    pub fn slower(&mut self) {
+        if let Some(speed) = SPEEDS.iter().rev().find(|s| **s < self.speed) {
+            self.speed = *speed;
+        }
+    }
+
+    /// Next speed up in SPEEDS
+    pub fn faster(&mut self) {
+        if let Some(speed) = SPEEDS.iter().find(|s| **s > self.speed) {
+            self.speed = *speed;
+        }
+    }
+
+    /// Pause if running, otherwise advance one frame and stay paused
+    pub fn step(&mut self) {
+        self.paused = true;
+        if self.step == Step::Idle {
+            self.step = Step::Requested;
+        }
+    }
+}
+
+/// Plugin for pausing, slowing down, speeding up and stepping the scene
+pub struct ClockPlugin;
+
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        register_action(app, "pause", "Pause", vec![Binding::Key(KeyCode::Space)]);
+        register_action(
+            app,
+            "slower",
+            "Slow Down",
+            vec![Binding::Key(KeyCode::BracketLeft)],
+        );
+        register_action(
+            app,
+            "faster",
+            "Speed Up",
+            vec![Binding::Key(KeyCode::BracketRight)],
+        );
+        register_action(
+            app,
+            "step",
+            "Step One Frame",
+            vec![Binding::Key(KeyCode::Period)],
+        );
+        register_action(
+            app,
+            "normal_speed",
+            "Normal Speed",
+            vec![Binding::Key(KeyCode::Backslash)],
+        );
+
+        app.init_resource::<TimeControl>()
+            .add_systems(Update, (time_from_actions, apply_time_control).chain());
+    }
+}
+
+fn time_from_actions(actions: Res<InputActions>, mut control: ResMut<TimeControl>) {
+    if actions.just_triggered("pause") {
+        control.paused = !control.paused;
+    }
+    if actions.just_triggered("slower") {
+        control.slower();
+    }
+    if actions.just_triggered("faster") {
+        control.faster();
+    }
+    if actions.just_triggered("step") {
+        control.step();
+    }
+    if actions.just_triggered("normal_speed") {
+        control.speed = 1.0;
+    }
+}
+
+/// Push TimeControl onto Time<Virtual>. A step unpauses for exactly one time
+/// update, this frame's delta is already set by now so it's the next one that
+/// moves and the one after gets paused again.
+fn apply_time_control(mut control: ResMut<TimeControl>, mut time: ResMut<Time<Virtual>>) {
+    if time.relative_speed() != control.speed {
+        time.set_relative_speed(control.speed);
+    }
+
+    match control.step {
+        Step::Requested => {
+            time.unpause();
+            control.step = Step::Running;
+        }
+        Step::Running => {
+            time.pause();
+            control.step = Step::Idle;
+        }
+        Step::Idle if control.paused != time.is_paused() => {
+            if control.paused {
+                time.pause();
+            } else {
+                time.unpause();
+            }
+        }
+        Step::Idle => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::{TimePlugin, TimeUpdateStrategy};
+
+    use super::*;
+
+    #[test]
+    fn test_slower_faster_stop_at_the_ends() {
+        let mut control = TimeControl::default();
+        control.faster();
+        assert_eq!(control.speed, 2.0);
+        for _ in 0..10 {
+            control.faster();
+        }
+        assert_eq!(control.speed, 4.0);
+        for _ in 0..10 {
+            control.slower();
+        }
+        assert_eq!(control.speed, 0.1);
+    }
+
+    #[test]
+    fn test_step_advances_one_frame() {
+        let frame = Duration::from_millis(10);
+        let mut app = App::new();
+        app.add_plugins(TimePlugin)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(frame))
+            .init_resource::<TimeControl>()
+            .add_systems(Update, apply_time_control);
+        let elapsed = |app: &App| app.world().resource::<Time<Virtual>>().elapsed();
+
+        app.update();
+        app.world_mut().resource_mut::<TimeControl>().paused = true;
+        app.update();
+        let paused_at = elapsed(&app);
+        app.update();
+        app.update();
+        assert_eq!(elapsed(&app), paused_at);
+
+        app.world_mut().resource_mut::<TimeControl>().step();
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(elapsed(&app), paused_at + frame);
+        assert!(app.world().resource::<TimeControl>().paused);
+    }
+}
@@ -0,0 +1,356 @@
+use bevy::prelude::*;
+use bevy_old_tv_shader::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{TvSettingsResource, default_tv_settings};
+
+/// Default time in seconds to blend from one preset to another
+const DEFAULT_BLEND_SECS: f32 = 1.0;
+
+/// Serializable copy of the OldTvSettings fields, the shader crate doesn't do
+/// serde so presets round trip through this instead.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CrtParams {
+    pub screen_shape_factor: f32,
+    pub rows: f32,
+    pub brightness: f32,
+    pub edges_transition_size: f32,
+    pub channels_mask_min: f32,
+}
+
+impl Default for CrtParams {
+    fn default() -> Self {
+        default_tv_settings().into()
+    }
+}
+
+impl From<OldTvSettings> for CrtParams {
+    fn from(settings: OldTvSettings) -> Self {
+        Self {
+            screen_shape_factor: settings.screen_shape_factor,
+            rows: settings.rows,
+            brightness: settings.brightness,
+            edges_transition_size: settings.edges_transition_size,
+            channels_mask_min: settings.channels_mask_min,
+        }
+    }
+}
+
+impl From<CrtParams> for OldTvSettings {
+    #[allow(clippy::field_reassign_with_default)]
+    fn from(params: CrtParams) -> Self {
+        let mut settings = OldTvSettings::default();
+        settings.screen_shape_factor = params.screen_shape_factor;
+        settings.rows = params.rows;
+        settings.brightness = params.brightness;
+        settings.edges_transition_size = params.edges_transition_size;
+        settings.channels_mask_min = params.channels_mask_min;
+        settings
+    }
+}
+
+impl CrtParams {
+    /// No effect for a picture `height` pixels tall, one scanline per pixel
+    /// row lands every pixel on a scanline centre which the shader leaves
+    /// alone. The tv toggle fades from/to this.
+    pub fn neutral(height: f32) -> Self {
+        Self {
+            screen_shape_factor: 0.0,
+            rows: height,
+            brightness: 1.0,
+            edges_transition_size: 0.0,
+            channels_mask_min: 1.0,
+        }
+    }
+
+    /// Linear interpolation of every field, t of 0 is self 1 is other
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        Self {
+            screen_shape_factor: self.screen_shape_factor.lerp(other.screen_shape_factor, t),
+            rows: self.rows.lerp(other.rows, t),
+            brightness: self.brightness.lerp(other.brightness, t),
+            edges_transition_size: self
+                .edges_transition_size
+                .lerp(other.edges_transition_size, t),
+            channels_mask_min: self.channels_mask_min.lerp(other.channels_mask_min, t),
+        }
+    }
+}
+
+/// A named crt look
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CrtPreset {
+    pub name: String,
+    pub params: CrtParams,
+}
+
+impl CrtPreset {
+    pub fn new(name: &str, params: CrtParams) -> Self {
+        Self {
+            name: name.to_string(),
+            params,
+        }
+    }
+}
+
+/// The looks that ship with the site, first one is what setup uses
+pub fn builtin_presets() -> Vec<CrtPreset> {
+    vec![
+        CrtPreset::new("default", CrtParams::default()),
+        CrtPreset::new(
+            "arcade",
+            CrtParams {
+                screen_shape_factor: 0.1,
+                rows: 240.0,
+                brightness: 2.5,
+                edges_transition_size: 0.01,
+                channels_mask_min: 0.3,
+            },
+        ),
+        CrtPreset::new(
+            "broken tv",
+            CrtParams {
+                screen_shape_factor: 0.6,
+                rows: 48.0,
+                brightness: 4.5,
+                edges_transition_size: 0.2,
+                channels_mask_min: 0.0,
+            },
+        ),
+        CrtPreset::new(
+            "subtle scanlines",
+            CrtParams {
+                screen_shape_factor: 0.02,
+                rows: 320.0,
+                brightness: 1.4,
+                edges_transition_size: 0.005,
+                channels_mask_min: 0.7,
+            },
+        ),
+    ]
+}
+
+/// Serialize user presets to ron for export/persistence
+pub fn presets_to_ron(presets: &[CrtPreset]) -> ron::Result<String> {
+    ron::ser::to_string_pretty(presets, ron::ser::PrettyConfig::default())
+}
+
+/// Parse presets previously written by presets_to_ron
+pub fn presets_from_ron(text: &str) -> Result<Vec<CrtPreset>, ron::error::SpannedError> {
+    ron::from_str(text)
+}
+
+/// All known presets plus which one is active
+#[derive(Resource)]
+pub struct CrtPresets {
+    pub builtin: Vec<CrtPreset>,
+    pub user: Vec<CrtPreset>,
+    /// Name of the last selected preset, None once the sliders are touched
+    pub active: Option<String>,
+    /// How long switching presets takes in seconds
+    pub blend_secs: f32,
+}
+
+impl Default for CrtPresets {
+    fn default() -> Self {
+        Self {
+            builtin: builtin_presets(),
+            user: Vec::new(),
+            active: Some("default".to_string()),
+            blend_secs: DEFAULT_BLEND_SECS,
+        }
+    }
+}
+
+impl CrtPresets {
+    /// Iterate builtin presets then user presets
+    pub fn iter(&self) -> impl Iterator<Item = &CrtPreset> {
+        self.builtin.iter().chain(self.user.iter())
+    }
+
+    /// Add or replace a user preset by name, returning the name it went in
+    /// under. One named after a builtin gets renamed, otherwise the builtin
+    /// would hide it in the picker and share links.
+    pub fn save_user(&mut self, mut preset: CrtPreset) -> String {
+        if self.builtin.iter().any(|p| p.name == preset.name) {
+            preset.name = format!("{} (custom)", preset.name);
+        }
+        let name = preset.name.clone();
+        if let Some(existing) = self.user.iter_mut().find(|p| p.name == preset.name) {
+            *existing = preset;
+        } else {
+            self.user.push(preset);
+        }
+        name
+    }
+}
+
+/// In progress blend between two sets of crt params
+#[derive(Resource)]
+pub struct CrtBlend {
+    from: CrtParams,
+    to: CrtParams,
+    elapsed: f32,
+    duration: f32,
+}
+
+impl CrtBlend {
+    pub fn new(from: CrtParams, to: CrtParams, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            elapsed: 0.0,
+            duration,
+        }
+    }
+
+    /// Eased 0..1 progress of the blend
+    fn progress(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        let t = (self.elapsed / self.duration).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+/// Plugin for crt presets and blending between them
+pub struct CrtPresetPlugin;
+
+impl Plugin for CrtPresetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CrtPresets>()
+            .add_systems(Update, blend_crt.run_if(resource_exists::<CrtBlend>));
+    }
+}
+
+/// Step the active blend and write the result into the tv settings, real time
+/// so it still finishes with the scene paused
+fn blend_crt(
+    time: Res<Time<Real>>,
+    mut blend: ResMut<CrtBlend>,
+    mut tv_settings: ResMut<TvSettingsResource>,
+    mut commands: Commands,
+) {
+    blend.elapsed += time.delta_secs();
+
+    let t = blend.progress();
+    tv_settings.settings = blend.from.lerp(blend.to, t).into();
+
+    if t >= 1.0 {
+        commands.remove_resource::<CrtBlend>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lerp_endpoints() {
+        let presets = builtin_presets();
+        let a = presets[0].params;
+        let b = presets[1].params;
+
+        assert_eq!(a.lerp(b, 0.0), a);
+
+        let end = a.lerp(b, 1.0);
+        assert!((end.rows - b.rows).abs() < 1e-4);
+        assert!((end.brightness - b.brightness).abs() < 1e-4);
+        assert!((end.channels_mask_min - b.channels_mask_min).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_lerp_midpoint() {
+        let a = CrtParams {
+            screen_shape_factor: 0.0,
+            rows: 100.0,
+            brightness: 1.0,
+            edges_transition_size: 0.0,
+            channels_mask_min: 0.0,
+        };
+        let b = CrtParams {
+            screen_shape_factor: 1.0,
+            rows: 200.0,
+            brightness: 3.0,
+            edges_transition_size: 0.5,
+            channels_mask_min: 1.0,
+        };
+        let mid = a.lerp(b, 0.5);
+        assert_eq!(mid.rows, 150.0);
+        assert_eq!(mid.brightness, 2.0);
+        assert_eq!(mid.edges_transition_size, 0.25);
+    }
+
+    #[test]
+    fn test_blend_progress() {
+        let params = CrtParams::default();
+        let mut blend = CrtBlend::new(params, params, 2.0);
+        assert_eq!(blend.progress(), 0.0);
+        blend.elapsed = 1.0;
+        assert_eq!(blend.progress(), 0.5);
+        blend.elapsed = 5.0;
+        assert_eq!(blend.progress(), 1.0);
+
+        // Zero duration should just snap
+        assert_eq!(CrtBlend::new(params, params, 0.0).progress(), 1.0);
+    }
+
+    #[test]
+    fn test_presets_ron_roundtrip() {
+        let presets = builtin_presets();
+        let text = presets_to_ron(&presets).unwrap();
+        assert_eq!(presets_from_ron(&text).unwrap(), presets);
+    }
+
+    #[test]
+    fn test_save_user_replaces_by_name() {
+        let mut presets = CrtPresets::default();
+        presets.save_user(CrtPreset::new("mine", CrtParams::default()));
+        let changed = CrtParams {
+            rows: 10.0,
+            ..default()
+        };
+        presets.save_user(CrtPreset::new("mine", changed));
+
+        assert_eq!(presets.user.len(), 1);
+        assert_eq!(presets.user[0].params.rows, 10.0);
+    }
+
+    #[test]
+    fn test_save_user_never_shadows_a_builtin() {
+        let mut presets = CrtPresets::default();
+        let changed = CrtParams {
+            rows: 10.0,
+            ..default()
+        };
+        let name = presets.save_user(CrtPreset::new("arcade", changed));
+
+        assert_eq!(name, "arcade (custom)");
+        assert_eq!(presets.user[0].name, name);
+        let arcade = presets.iter().find(|p| p.name == "arcade").unwrap();
+        assert_eq!(arcade.params.rows, 240.0);
+    }
+
+    #[test]
+    fn test_neutral_leaves_every_pixel_alone() {
+        // The shader's scanline darkening, 0 at a row's centre and 1 between
+        let darkening = |pixel: f32, rows: f32, height: f32| {
+            let f = (((pixel + 0.5) / height * rows).fract() - 0.5).abs() * 2.0;
+            f * f
+        };
+
+        let height = 1080.0;
+        let neutral = CrtParams::neutral(height);
+        for pixel in 0..1080 {
+            assert!(darkening(pixel as f32, neutral.rows, height) < 1e-3);
+        }
+        // Unlike keeping the preset's rows, which darkens most of them
+        let full = CrtParams::default();
+        assert!(darkening(0.0, full.rows, height) > 0.5);
+
+        assert_eq!(neutral.screen_shape_factor, 0.0);
+        assert_eq!(neutral.brightness, 1.0);
+        assert_eq!(neutral.channels_mask_min, 1.0);
+    }
+}
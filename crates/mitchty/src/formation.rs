@@ -0,0 +1,526 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::motion::{MotionModel, Rotator};
+use crate::quality::Quality;
+use crate::tuning::SceneTuning;
+use crate::{GOLDEN_ANGLE, SceneRng};
+
+/// Side length of every cube
+const CUBE_SIZE: f32 = 0.5;
+/// Most cubes any formation can ask for
+pub const MAX_CUBES: usize = 1000;
+/// Scale cubes grow from and shrink to, zero scale upsets the renderer
+const MIN_SCALE: f32 = 0.001;
+
+/// Easing curves offered in the ui
+pub const EASINGS: [(&str, EaseFunction); 7] = [
+    ("Linear", EaseFunction::Linear),
+    ("Sine In Out", EaseFunction::SineInOut),
+    ("Quadratic In Out", EaseFunction::QuadraticInOut),
+    ("Cubic In Out", EaseFunction::CubicInOut),
+    ("Back Out", EaseFunction::BackOut),
+    ("Elastic Out", EaseFunction::ElasticOut),
+    ("Bounce Out", EaseFunction::BounceOut),
+];
+
+/// Name of an easing curve for the ui
+pub fn easing_label(easing: EaseFunction) -> &'static str {
+    EASINGS
+        .iter()
+        .find(|(_, e)| *e == easing)
+        .map_or("Custom", |(label, _)| *label)
+}
+
+/// Shape the cubes are laid out in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FormationKind {
+    /// N by M by K box
+    #[default]
+    Grid,
+    /// Flat circle
+    Ring,
+    /// Evenly spread over a sphere
+    Sphere,
+    /// Corkscrew going up
+    Helix,
+    /// Sunflower seed spiral, each cube GOLDEN_ANGLE around from the last. The
+    /// real one, not SceneTuning's, anything else and the spiral falls apart.
+    Phyllotaxis,
+}
+
+impl FormationKind {
+    pub const ALL: [FormationKind; 5] = [
+        FormationKind::Grid,
+        FormationKind::Ring,
+        FormationKind::Sphere,
+        FormationKind::Helix,
+        FormationKind::Phyllotaxis,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FormationKind::Grid => "Grid",
+            FormationKind::Ring => "Ring",
+            FormationKind::Sphere => "Sphere",
+            FormationKind::Helix => "Helix",
+            FormationKind::Phyllotaxis => "Phyllotaxis",
+        }
+    }
+}
+
+/// Current formation, change it and the cubes follow
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Formation {
+    pub kind: FormationKind,
+    /// Grid size along x, y and z
+    pub grid: UVec3,
+    /// Number of cubes for everything but the grid
+    pub count: usize,
+    /// Roughly how far apart neighbouring cubes are
+    pub spacing: f32,
+}
+
+impl Default for Formation {
+    /// The original flat 3x3 grid
+    fn default() -> Self {
+        Self {
+            kind: FormationKind::Grid,
+            grid: UVec3::new(3, 1, 3),
+            count: 9,
+            spacing: 1.0,
+        }
+    }
+}
+
+impl Formation {
+    /// How many cubes this formation has
+    pub fn cube_count(&self) -> usize {
+        match self.kind {
+            FormationKind::Grid => (self.grid.x * self.grid.y * self.grid.z) as usize,
+            _ => self.count,
+        }
+        .min(MAX_CUBES)
+    }
+
+    /// Positions for at most max cubes. The shapes with a count spread the
+    /// fewer cubes over the whole shape, grids just lose the last few.
+    pub fn positions_up_to(&self, max: usize) -> Vec<Vec3> {
+        let limited = Formation {
+            count: self.count.min(max),
+            ..self.clone()
+        };
+        let mut positions = limited.positions();
+        positions.truncate(max);
+        positions
+    }
+
+    /// Where every cube goes, centered on the origin
+    pub fn positions(&self) -> Vec<Vec3> {
+        let n = self.cube_count();
+        let s = self.spacing;
+        let golden = GOLDEN_ANGLE.to_radians();
+
+        match self.kind {
+            FormationKind::Grid => {
+                let offset = (self.grid.as_vec3() - Vec3::ONE) * 0.5;
+                let mut positions = Vec::with_capacity(n);
+                for x in 0..self.grid.x {
+                    for y in 0..self.grid.y {
+                        for z in 0..self.grid.z {
+                            positions.push((UVec3::new(x, y, z).as_vec3() - offset) * s);
+                        }
+                    }
+                }
+                positions.truncate(n);
+                positions
+            }
+            FormationKind::Ring => {
+                // Big enough that neighbours are spacing apart along the circle
+                let radius = (n as f32 * s / TAU).max(s * 0.5);
+                (0..n)
+                    .map(|i| {
+                        let angle = i as f32 / n as f32 * TAU;
+                        Vec3::new(angle.cos(), 0.0, angle.sin()) * radius
+                    })
+                    .collect()
+            }
+            FormationKind::Sphere => {
+                // Fibonacci sphere, each cube gets about spacing² of surface
+                let radius = (s * (n as f32 / (4.0 * PI)).sqrt()).max(s * 0.5);
+                (0..n)
+                    .map(|i| {
+                        let y = 1.0 - 2.0 * (i as f32 + 0.5) / n as f32;
+                        let around = (1.0 - y * y).sqrt();
+                        let angle = i as f32 * golden;
+                        Vec3::new(angle.cos() * around, y, angle.sin() * around) * radius
+                    })
+                    .collect()
+            }
+            FormationKind::Helix => {
+                let radius = s * 1.5;
+                let rise = s * 0.15;
+                let middle = (n as f32 - 1.0) * 0.5;
+                (0..n)
+                    .map(|i| {
+                        // Neighbours spacing apart along the curve
+                        let angle = i as f32 * s / radius;
+                        Vec3::new(
+                            angle.cos() * radius,
+                            (i as f32 - middle) * rise,
+                            angle.sin() * radius,
+                        )
+                    })
+                    .collect()
+            }
+            FormationKind::Phyllotaxis => {
+                // Vogel's model, 0.6 gets nearest neighbours about spacing apart
+                (0..n)
+                    .map(|i| {
+                        let radius = s * 0.6 * (i as f32 + 0.5).sqrt();
+                        let angle = i as f32 * golden;
+                        Vec3::new(angle.cos(), 0.0, angle.sin()) * radius
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Which position in the formation a cube has
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FormationSlot(pub usize);
+
+/// How cubes get from one formation to the next
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct MorphSettings {
+    /// Seconds each cube takes to move
+    pub duration: f32,
+    /// Seconds between the first and last cube starting to move
+    pub stagger: f32,
+    pub easing: EaseFunction,
+}
+
+impl Default for MorphSettings {
+    fn default() -> Self {
+        Self {
+            duration: 1.2,
+            stagger: 0.6,
+            easing: EaseFunction::CubicInOut,
+        }
+    }
+}
+
+/// A cube on its way to its slot, only translation and scale are touched so
+/// the Rotator keeps it spinning the whole way
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Morph {
+    from: Vec3,
+    to: Vec3,
+    from_scale: f32,
+    to_scale: f32,
+    /// Seconds to wait before starting
+    delay: f32,
+    elapsed: f32,
+    /// Gone once it gets there, for cubes the new formation has no room for
+    despawn: bool,
+}
+
+impl Morph {
+    fn new(from: Vec3, to: Vec3, from_scale: f32, to_scale: f32, delay: f32) -> Self {
+        Self {
+            from,
+            to,
+            from_scale,
+            to_scale,
+            delay,
+            elapsed: 0.0,
+            despawn: false,
+        }
+    }
+
+    /// Advance by dt, returns translation, scale and whether it's done
+    fn step(&mut self, dt: f32, settings: &MorphSettings) -> (Vec3, f32, bool) {
+        self.elapsed += dt;
+
+        let t = if settings.duration <= 0.0 {
+            1.0
+        } else {
+            ((self.elapsed - self.delay) / settings.duration).clamp(0.0, 1.0)
+        };
+        let eased = settings.easing.sample_clamped(t);
+
+        (
+            self.from.lerp(self.to, eased),
+            self.from_scale.lerp(self.to_scale, eased).max(MIN_SCALE),
+            t >= 1.0,
+        )
+    }
+}
+
+/// Plugin laying out the cubes, and relaying them out when Formation changes
+pub struct FormationPlugin;
+
+impl Plugin for FormationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Formation>()
+            .init_resource::<MorphSettings>()
+            .add_systems(
+                Update,
+                (
+                    sync_formation
+                        .run_if(resource_changed::<Formation>.or(resource_changed::<Quality>)),
+                    animate_morphs,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Morph cubes that still have a slot over, shrink away the extras and grow in
+/// any missing. The very first layout just appears.
+#[allow(clippy::too_many_arguments)]
+pub fn sync_formation(
+    mut commands: Commands,
+    formation: Res<Formation>,
+    settings: Res<MorphSettings>,
+    tuning: Res<SceneTuning>,
+    quality: Res<Quality>,
+    mut rng: ResMut<SceneRng>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut mesh: Local<Option<Handle<Mesh>>>,
+    mut cubes: Query<(Entity, &FormationSlot, &Transform, &mut Rotator)>,
+    models: Query<&MotionModel>,
+) {
+    let positions = formation.positions_up_to(quality.current().max_cubes);
+    let mesh = mesh
+        .get_or_insert_with(|| meshes.add(Cuboid::new(CUBE_SIZE, CUBE_SIZE, CUBE_SIZE)))
+        .clone();
+    // New cubes move like the others
+    let model = models.iter().next().copied().unwrap_or_default();
+
+    let instant = cubes.is_empty();
+    // Cubes on their way out start as late as the last one in
+    let delay =
+        |slot: usize| settings.stagger * (slot as f32 / positions.len().max(1) as f32).min(1.0);
+
+    let mut filled = vec![false; positions.len()];
+    for (entity, slot, transform, mut rotator) in cubes.iter_mut() {
+        let from = transform.translation;
+        let scale = transform.scale.x;
+
+        match positions.get(slot.0) {
+            Some(position) => {
+                // The wave goes by where it's headed, not where it used to be
+                rotator.phase = Rotator::phase_at(position.xz());
+                commands.entity(entity).insert(Morph::new(
+                    from,
+                    *position,
+                    scale,
+                    1.0,
+                    delay(slot.0),
+                ));
+                filled[slot.0] = true;
+            }
+            None => {
+                // Out of the formation now so later changes leave it be
+                let mut morph = Morph::new(from, from, scale, MIN_SCALE, delay(slot.0));
+                morph.despawn = true;
+                commands
+                    .entity(entity)
+                    .remove::<FormationSlot>()
+                    .insert(morph);
+            }
+        }
+    }
+
+    let rng = &mut rng.0;
+    let (min, max) = tuning.cubes.speed_bounds();
+    for (slot, position) in positions.iter().enumerate() {
+        if filled[slot] {
+            continue;
+        }
+
+        let base_speed = Vec3::new(
+            rng.random_range(min..=max),
+            rng.random_range(min..=max),
+            rng.random_range(min..=max),
+        );
+
+        let scale = if instant { 1.0 } else { MIN_SCALE };
+
+        let mut cube = commands.spawn((
+            Mesh3d(mesh.clone()),
+            // The palette paints it
+            MeshMaterial3d(materials.add(StandardMaterial::default())),
+            Transform::from_translation(*position).with_scale(Vec3::splat(scale)),
+            Rotator::new(base_speed, position.xz(), rng.random_range(0.0..1000.0)),
+            model,
+            FormationSlot(slot),
+        ));
+        if !instant {
+            cube.insert(Morph::new(*position, *position, scale, 1.0, delay(slot)));
+        }
+    }
+}
+
+/// Move and scale morphing cubes, spinning is left to the Rotator
+fn animate_morphs(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<MorphSettings>,
+    mut query: Query<(Entity, &mut Transform, &mut Morph)>,
+) {
+    for (entity, mut transform, mut morph) in query.iter_mut() {
+        let (translation, scale, done) = morph.step(time.delta_secs(), &settings);
+        transform.translation = translation;
+        transform.scale = Vec3::splat(scale);
+
+        if done && morph.despawn {
+            commands.entity(entity).despawn();
+        } else if done {
+            commands.entity(entity).remove::<Morph>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_original_grid() {
+        let mut expected = Vec::new();
+        for x in -1..2 {
+            for z in -1..2 {
+                expected.push(Vec3::new(x as f32, 0.0, z as f32));
+            }
+        }
+        assert_eq!(Formation::default().positions(), expected);
+    }
+
+    #[test]
+    fn test_every_kind_has_count_positions() {
+        for kind in FormationKind::ALL {
+            let formation = Formation {
+                kind,
+                grid: UVec3::new(2, 3, 4),
+                count: 17,
+                ..default()
+            };
+            let expected = if kind == FormationKind::Grid { 24 } else { 17 };
+            assert_eq!(formation.positions().len(), expected, "{kind:?}");
+            assert!(formation.positions().iter().all(|p| p.is_finite()));
+        }
+    }
+
+    #[test]
+    fn test_count_is_capped() {
+        let formation = Formation {
+            grid: UVec3::splat(20),
+            ..default()
+        };
+        assert_eq!(formation.positions().len(), MAX_CUBES);
+    }
+
+    #[test]
+    fn test_positions_up_to_spreads_fewer_cubes() {
+        let ring = Formation {
+            kind: FormationKind::Ring,
+            count: 100,
+            ..default()
+        };
+        let limited = ring.positions_up_to(10);
+        assert_eq!(limited.len(), 10);
+        // Still a whole ring, just a smaller one
+        assert!((limited[5] + limited[0]).length() < 1e-4);
+        assert_eq!(Formation::default().positions_up_to(4).len(), 4);
+        assert_eq!(ring.positions_up_to(MAX_CUBES), ring.positions());
+    }
+
+    #[test]
+    fn test_morph_waits_then_eases_to_target() {
+        let settings = MorphSettings {
+            duration: 1.0,
+            easing: EaseFunction::Linear,
+            ..default()
+        };
+        let mut morph = Morph::new(Vec3::ZERO, Vec3::X * 2.0, 1.0, 1.0, 0.5);
+
+        // Still waiting on its stagger
+        assert_eq!(morph.step(0.25, &settings), (Vec3::ZERO, 1.0, false));
+
+        let (halfway, _, done) = morph.step(0.75, &settings);
+        assert!((halfway.x - 1.0).abs() < 1e-5);
+        assert!(!done);
+
+        assert_eq!(morph.step(1.0, &settings), (Vec3::X * 2.0, 1.0, true));
+    }
+
+    #[test]
+    fn test_morph_scale_never_hits_zero() {
+        let settings = MorphSettings {
+            easing: EaseFunction::BackOut,
+            ..default()
+        };
+        let mut morph = Morph::new(Vec3::ZERO, Vec3::ZERO, 1.0, MIN_SCALE, 0.0);
+        for _ in 0..100 {
+            let (_, scale, _) = morph.step(0.02, &settings);
+            assert!(scale >= MIN_SCALE);
+        }
+    }
+
+    #[test]
+    fn test_ring_neighbours_are_spacing_apart() {
+        let formation = Formation {
+            kind: FormationKind::Ring,
+            count: 40,
+            spacing: 1.5,
+            ..default()
+        };
+        let positions = formation.positions();
+        let gap = positions[0].distance(positions[1]);
+        assert!((gap - 1.5).abs() < 0.01, "{gap}");
+    }
+
+    #[test]
+    fn test_phyllotaxis_steps_by_golden_angle() {
+        let formation = Formation {
+            kind: FormationKind::Phyllotaxis,
+            count: 3,
+            ..default()
+        };
+        let positions = formation.positions();
+        let a = positions[1].z.atan2(positions[1].x);
+        let b = positions[2].z.atan2(positions[2].x);
+        let step = (b - a).rem_euclid(TAU).to_degrees();
+        assert!((step - GOLDEN_ANGLE).abs() < 1e-3, "{step}");
+    }
+
+    #[test]
+    fn test_moved_cubes_take_their_new_slots_phase() {
+        let mut app = App::new();
+        app.init_resource::<Formation>()
+            .init_resource::<MorphSettings>()
+            .init_resource::<SceneTuning>()
+            .init_resource::<Quality>()
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<StandardMaterial>>()
+            .insert_resource(SceneRng::new(1))
+            .add_systems(Update, sync_formation.run_if(resource_changed::<Formation>));
+        app.update();
+
+        app.world_mut().resource_mut::<Formation>().kind = FormationKind::Ring;
+        app.update();
+
+        let positions = app.world().resource::<Formation>().positions();
+        let mut cubes = app.world_mut().query::<(&FormationSlot, &Rotator)>();
+        assert!(cubes.iter(app.world()).count() > 0);
+        for (slot, rotator) in cubes.iter(app.world()) {
+            assert_eq!(rotator.phase, Rotator::phase_at(positions[slot.0].xz()));
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::prelude::*;
+
+use crate::CubeSpeedScale;
+use crate::camera::OrbitInput;
+
+/// Drag equivalent in logical pixels per second of a fully pushed left stick
+const STICK_DRAG_PER_SEC: f32 = 400.0;
+/// Log radius change per second of a fully pushed right stick
+const STICK_ZOOM_PER_SEC: f32 = 1.5;
+/// Cube speed multiplier with the right trigger fully pulled, the left
+/// trigger slows down to a stop
+const MAX_TRIGGER_SPEED: f32 = 3.0;
+
+/// Plugin for the analog side of gamepads, buttons go through input actions
+pub struct GamepadControlPlugin;
+
+impl Plugin for GamepadControlPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (scale_cube_speed, log_gamepad_connections));
+    }
+}
+
+/// Orbit input from the sticks, left orbits/raises and right zooms
+pub fn stick_orbit(left: Vec2, right: Vec2, dt: f32) -> OrbitInput {
+    OrbitInput {
+        drag: left * STICK_DRAG_PER_SEC * dt,
+        // Pushing up zooms in
+        zoom: (-right.y * STICK_ZOOM_PER_SEC * dt).exp(),
+    }
+}
+
+/// Combined orbit input of every connected gamepad
+pub fn gamepad_orbit<'a>(gamepads: impl Iterator<Item = &'a Gamepad>, dt: f32) -> OrbitInput {
+    gamepads.fold(OrbitInput::default(), |total, gamepad| {
+        let input = stick_orbit(gamepad.left_stick(), gamepad.right_stick(), dt);
+        OrbitInput {
+            drag: total.drag + input.drag,
+            zoom: total.zoom * input.zoom,
+        }
+    })
+}
+
+/// Cube speed multiplier for the trigger positions, 1 with neither pulled
+pub fn trigger_speed_scale(left: f32, right: f32) -> f32 {
+    (1.0 + right * (MAX_TRIGGER_SPEED - 1.0) - left).max(0.0)
+}
+
+/// Triggers speed up/slow down the cubes, whichever pad is furthest from
+/// normal speed wins
+fn scale_cube_speed(gamepads: Query<&Gamepad>, mut scale: ResMut<CubeSpeedScale>) {
+    let wanted = gamepads
+        .iter()
+        .map(|g| {
+            trigger_speed_scale(
+                g.get(GamepadButton::LeftTrigger2).unwrap_or(0.0),
+                g.get(GamepadButton::RightTrigger2).unwrap_or(0.0),
+            )
+        })
+        .max_by(|a, b| (a - 1.0).abs().total_cmp(&(b - 1.0).abs()))
+        .unwrap_or(1.0);
+
+    scale.set_if_neq(CubeSpeedScale(wanted));
+}
+
+/// Log pads coming and going by name, bevy only logs the entity
+fn log_gamepad_connections(mut reader: MessageReader<GamepadConnectionEvent>) {
+    for event in reader.read() {
+        match &event.connection {
+            GamepadConnection::Connected { name, .. } => info!("gamepad {name} connected"),
+            GamepadConnection::Disconnected => info!("gamepad {} disconnected", event.gamepad),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trigger_speed_scale() {
+        assert_eq!(trigger_speed_scale(0.0, 0.0), 1.0);
+        assert_eq!(trigger_speed_scale(0.0, 1.0), MAX_TRIGGER_SPEED);
+        assert_eq!(trigger_speed_scale(1.0, 0.0), 0.0);
+        assert_eq!(trigger_speed_scale(1.0, 1.0), MAX_TRIGGER_SPEED - 1.0);
+    }
+
+    #[test]
+    fn test_idle_sticks_do_nothing() {
+        assert_eq!(
+            stick_orbit(Vec2::ZERO, Vec2::ZERO, 0.016),
+            OrbitInput::default()
+        );
+    }
+
+    #[test]
+    fn test_sticks_combine_across_pads() {
+        let mut a = Gamepad::default();
+        a.analog_mut().set(GamepadAxis::LeftStickX, 1.0);
+        let mut b = Gamepad::default();
+        b.analog_mut().set(GamepadAxis::LeftStickX, 0.5);
+        b.analog_mut().set(GamepadAxis::RightStickY, 1.0);
+
+        let input = gamepad_orbit([a, b].iter(), 0.5);
+        assert_eq!(input.drag, Vec2::new(1.5 * STICK_DRAG_PER_SEC * 0.5, 0.0));
+        assert!(input.zoom < 1.0);
+    }
+}
@@ -0,0 +1,340 @@
+use bevy::input::InputSystems;
+use bevy::prelude::*;
+use bevy_egui::input::EguiWantsInput;
+
+use crate::input::Gesture;
+
+/// How far in logical pixels a finger can wander and still count as a tap
+const TAP_SLOP: f32 = 12.0;
+/// Longest a touch can be held and still count as a tap
+const TAP_MAX_SECS: f64 = 0.35;
+/// How long to hold still for a long press
+const LONG_PRESS_SECS: f64 = 0.6;
+/// Max gap between two taps for a double tap, a single tap is only reported
+/// once this passes without a second one
+const DOUBLE_TAP_SECS: f64 = 0.3;
+
+/// What the touch screen did this frame
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct TouchGestures {
+    /// Discrete gestures recognized this frame
+    pub fired: Vec<Gesture>,
+    /// One finger drag movement in logical pixels
+    pub drag: Vec2,
+    /// Two finger pinch as a ratio of finger distance, above 1 is spreading
+    pub pinch: f32,
+}
+
+impl Default for TouchGestures {
+    fn default() -> Self {
+        Self {
+            fired: Vec::new(),
+            drag: Vec2::ZERO,
+            pinch: 1.0,
+        }
+    }
+}
+
+/// State for the touch sequence in progress, a sequence runs from the first
+/// finger down to the last one up.
+#[derive(Default)]
+pub struct GestureRecognizer {
+    /// Currently down touches, id and position
+    down: Vec<(u64, Vec2)>,
+    /// When the sequence started
+    started: f64,
+    /// First finger position of the sequence
+    start_position: Vec2,
+    /// Most fingers down at once this sequence
+    max_fingers: usize,
+    /// Finger distance when the second finger went down
+    start_spread: Option<f32>,
+    /// Moved past the slop, no longer a tap/long press
+    moved: bool,
+    long_pressed: bool,
+    /// egui wanted the pointer at some point this sequence so it's egui's
+    captured: bool,
+    /// Single tap waiting to see if a second one turns it into a double tap
+    pending_tap: Option<f64>,
+}
+
+/// Distance between the first two touches
+fn spread(touches: &[(u64, Vec2)]) -> Option<f32> {
+    match touches {
+        [(_, a), (_, b), ..] => Some(a.distance(*b)),
+        _ => None,
+    }
+}
+
+impl GestureRecognizer {
+    /// Feed in the touches down this frame, once egui wants the pointer the
+    /// rest of the sequence is left to it.
+    pub fn update(
+        &mut self,
+        now: f64,
+        touches: &[(u64, Vec2)],
+        egui_wants_pointer: bool,
+    ) -> TouchGestures {
+        let mut out = TouchGestures::default();
+
+        if self.down.is_empty() && !touches.is_empty() {
+            *self = Self {
+                started: now,
+                start_position: touches[0].1,
+                captured: egui_wants_pointer,
+                pending_tap: self.pending_tap,
+                ..default()
+            };
+        }
+
+        // egui doesn't always know it's been touched until a frame later
+        if !touches.is_empty() {
+            self.captured |= egui_wants_pointer;
+        }
+
+        let was_down = !self.down.is_empty();
+        let previous = std::mem::replace(&mut self.down, touches.to_vec());
+
+        if !touches.is_empty() && !self.captured {
+            self.max_fingers = self.max_fingers.max(touches.len());
+
+            // Only compare like with like, a finger landing or lifting would
+            // otherwise look like a jump
+            let same_fingers = previous.len() == touches.len()
+                && previous.iter().zip(touches).all(|(a, b)| a.0 == b.0);
+
+            match touches {
+                [(_, position)] => {
+                    if position.distance(self.start_position) > TAP_SLOP {
+                        self.moved = true;
+                    }
+                    if self.moved && same_fingers && self.max_fingers == 1 {
+                        out.drag = *position - previous[0].1;
+                    }
+                }
+                _ => {
+                    let now_spread = spread(touches).unwrap_or_default();
+                    let start = *self.start_spread.get_or_insert(now_spread);
+                    if (now_spread - start).abs() > TAP_SLOP {
+                        self.moved = true;
+                    }
+                    if self.moved
+                        && same_fingers
+                        && let Some(before) = spread(&previous).filter(|s| *s > 0.0)
+                    {
+                        out.pinch = now_spread / before;
+                    }
+                }
+            }
+
+            if !self.moved
+                && !self.long_pressed
+                && self.max_fingers == 1
+                && now - self.started >= LONG_PRESS_SECS
+            {
+                self.long_pressed = true;
+                self.pending_tap = None;
+                out.fired.push(Gesture::LongPress);
+            }
+        }
+
+        // Last finger up ends the sequence
+        if was_down && touches.is_empty() && !self.captured {
+            let tap = !self.moved && !self.long_pressed && now - self.started <= TAP_MAX_SECS;
+
+            match (tap, self.max_fingers) {
+                (true, 1) => {
+                    if self.pending_tap.take().is_some() {
+                        out.fired.push(Gesture::DoubleTap);
+                    } else {
+                        self.pending_tap = Some(now);
+                    }
+                }
+                (true, 2) => out.fired.push(Gesture::TwoFingerTap),
+                _ => {}
+            }
+        }
+
+        // No second tap came so it was just a tap
+        if touches.is_empty()
+            && let Some(tapped) = self.pending_tap
+            && now - tapped > DOUBLE_TAP_SECS
+        {
+            self.pending_tap = None;
+            out.fired.push(Gesture::Tap);
+        }
+
+        out
+    }
+}
+
+/// Plugin recognizing touch gestures into TouchGestures every frame
+pub struct GesturePlugin;
+
+impl Plugin for GesturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchGestures>()
+            .add_systems(PreUpdate, recognize_gestures.after(InputSystems));
+    }
+}
+
+/// Run the recognizer against this frame's touches
+pub fn recognize_gestures(
+    touches: Res<Touches>,
+    time: Res<Time<Real>>,
+    egui_input: Option<Res<EguiWantsInput>>,
+    mut recognizer: Local<GestureRecognizer>,
+    mut gestures: ResMut<TouchGestures>,
+) {
+    let mut down: Vec<(u64, Vec2)> = touches.iter().map(|t| (t.id(), t.position())).collect();
+    down.sort_by_key(|(id, _)| *id);
+
+    let egui_wants_pointer = egui_input.is_some_and(|e| e.wants_any_pointer_input());
+    let result = recognizer.update(time.elapsed_secs_f64(), &down, egui_wants_pointer);
+
+    // Don't trip change detection every frame for nothing
+    gestures.set_if_neq(result);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f64 = 1.0 / 60.0;
+
+    /// Feed frames of touches starting at time t, returns everything fired
+    fn run(
+        recognizer: &mut GestureRecognizer,
+        t: &mut f64,
+        frames: &[&[(u64, Vec2)]],
+    ) -> Vec<TouchGestures> {
+        frames
+            .iter()
+            .map(|touches| {
+                *t += FRAME;
+                recognizer.update(*t, touches, false)
+            })
+            .collect()
+    }
+
+    fn fired(results: &[TouchGestures]) -> Vec<Gesture> {
+        results.iter().flat_map(|r| r.fired.clone()).collect()
+    }
+
+    fn idle(recognizer: &mut GestureRecognizer, t: &mut f64, secs: f64) -> Vec<Gesture> {
+        let frames = (secs / FRAME) as usize;
+        let empty: Vec<&[(u64, Vec2)]> = vec![&[]; frames];
+        fired(&run(recognizer, t, &empty))
+    }
+
+    #[test]
+    fn test_tap_waits_out_double_tap_window() {
+        let mut r = GestureRecognizer::default();
+        let mut t = 0.0;
+        let finger: &[(u64, Vec2)] = &[(0, Vec2::new(100.0, 100.0))];
+
+        assert!(fired(&run(&mut r, &mut t, &[finger, finger, &[]])).is_empty());
+        assert_eq!(idle(&mut r, &mut t, 0.5), vec![Gesture::Tap]);
+    }
+
+    #[test]
+    fn test_double_tap() {
+        let mut r = GestureRecognizer::default();
+        let mut t = 0.0;
+        let finger: &[(u64, Vec2)] = &[(0, Vec2::new(100.0, 100.0))];
+
+        let mut seen = fired(&run(&mut r, &mut t, &[finger, &[], &[], finger, &[]]));
+        seen.extend(idle(&mut r, &mut t, 0.5));
+        assert_eq!(seen, vec![Gesture::DoubleTap]);
+    }
+
+    #[test]
+    fn test_long_press() {
+        let mut r = GestureRecognizer::default();
+        let mut t = 0.0;
+        let finger: &[(u64, Vec2)] = &[(0, Vec2::new(100.0, 100.0))];
+
+        let held: Vec<&[(u64, Vec2)]> = vec![finger; 60];
+        let mut seen = fired(&run(&mut r, &mut t, &held));
+        seen.extend(idle(&mut r, &mut t, 0.5));
+        assert_eq!(seen, vec![Gesture::LongPress]);
+    }
+
+    #[test]
+    fn test_two_finger_tap() {
+        let mut r = GestureRecognizer::default();
+        let mut t = 0.0;
+        let fingers: &[(u64, Vec2)] = &[(0, Vec2::new(100.0, 100.0)), (1, Vec2::new(200.0, 100.0))];
+
+        let mut seen = fired(&run(&mut r, &mut t, &[fingers, fingers, &[]]));
+        seen.extend(idle(&mut r, &mut t, 0.5));
+        assert_eq!(seen, vec![Gesture::TwoFingerTap]);
+    }
+
+    #[test]
+    fn test_drag() {
+        let mut r = GestureRecognizer::default();
+        let mut t = 0.0;
+
+        let results = run(
+            &mut r,
+            &mut t,
+            &[
+                &[(0, Vec2::new(100.0, 100.0))],
+                &[(0, Vec2::new(120.0, 100.0))],
+                &[(0, Vec2::new(150.0, 110.0))],
+                &[],
+            ],
+        );
+        assert_eq!(results[1].drag, Vec2::new(20.0, 0.0));
+        assert_eq!(results[2].drag, Vec2::new(30.0, 10.0));
+        assert!(fired(&results).is_empty());
+        assert!(idle(&mut r, &mut t, 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_pinch() {
+        let mut r = GestureRecognizer::default();
+        let mut t = 0.0;
+
+        let results = run(
+            &mut r,
+            &mut t,
+            &[
+                &[(0, Vec2::new(100.0, 100.0)), (1, Vec2::new(200.0, 100.0))],
+                &[(0, Vec2::new(50.0, 100.0)), (1, Vec2::new(250.0, 100.0))],
+                &[],
+            ],
+        );
+        assert_eq!(results[1].pinch, 2.0);
+        assert!(fired(&results).is_empty());
+    }
+
+    #[test]
+    fn test_egui_capture_swallows_sequence() {
+        let mut r = GestureRecognizer::default();
+        let finger = [(0, Vec2::new(100.0, 100.0))];
+
+        r.update(0.0, &finger, true);
+        r.update(0.1, &[], false);
+        assert!(r.update(1.0, &[], false).fired.is_empty());
+    }
+
+    #[test]
+    fn test_egui_capture_after_touch_down() {
+        let mut r = GestureRecognizer::default();
+        let mut t = 0.0;
+        let finger = [(0, Vec2::new(100.0, 100.0))];
+        let dragged = [(0, Vec2::new(200.0, 100.0))];
+
+        r.update(t, &finger, false);
+        t += FRAME;
+        r.update(t, &finger, true);
+        t += FRAME;
+        // Dragging an egui slider doesn't orbit, nor is letting go a tap
+        assert_eq!(r.update(t, &dragged, false).drag, Vec2::ZERO);
+        t += FRAME;
+        r.update(t, &[], false);
+        assert!(idle(&mut r, &mut t, 0.5).is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
+
+use crate::input::{Binding, Gesture, InputActions, register_action};
+use crate::toggle::FeatureToggles;
+use crate::ui::binding_list;
+
+/// Whether the help overlay is up
+#[derive(Resource, Default)]
+pub struct HelpOverlay {
+    pub open: bool,
+}
+
+/// First visit hint pointing at the help overlay, dismissed is persisted so
+/// it only nags once.
+#[derive(Resource, Default)]
+pub struct HelpHint {
+    pub dismissed: bool,
+}
+
+/// One row of the help overlay
+#[derive(Debug, PartialEq)]
+pub struct HelpLine {
+    pub label: &'static str,
+    pub bindings: String,
+    /// Current state if the action is a feature toggle
+    pub enabled: Option<bool>,
+}
+
+/// Plugin for the ? help overlay and its first visit hint
+pub struct HelpPlugin;
+
+impl Plugin for HelpPlugin {
+    fn build(&self, app: &mut App) {
+        // Shift doesn't matter, ? and / are the same key
+        register_action(
+            app,
+            "help",
+            "Help",
+            vec![
+                Binding::Key(KeyCode::Slash),
+                Binding::Touch(Gesture::TwoFingerTap),
+            ],
+        );
+
+        app.init_resource::<HelpOverlay>()
+            .init_resource::<HelpHint>()
+            .add_systems(Update, toggle_help)
+            .add_systems(EguiPrimaryContextPass, help_ui);
+    }
+}
+
+/// Every registered action and what it's bound to, generated so the list can't
+/// go stale as toggles/actions get added.
+pub fn help_lines(actions: &InputActions, toggles: &FeatureToggles) -> Vec<HelpLine> {
+    actions
+        .entries()
+        .iter()
+        .map(|action| HelpLine {
+            label: action.label,
+            bindings: match binding_list(actions, action.id) {
+                list if list.is_empty() => "unbound".to_string(),
+                list => list,
+            },
+            enabled: toggles
+                .entries()
+                .iter()
+                .find(|t| t.id == action.id)
+                .map(|t| t.enabled),
+        })
+        .collect()
+}
+
+/// Open/close the overlay from its action, escape also closes it
+fn toggle_help(
+    actions: Res<InputActions>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<HelpOverlay>,
+    mut hint: ResMut<HelpHint>,
+) {
+    if actions.just_triggered("help") {
+        overlay.open = !overlay.open;
+        // Found it on their own, no need for the hint anymore
+        if !hint.dismissed {
+            hint.dismissed = true;
+        }
+    } else if overlay.open && keyboard.just_pressed(KeyCode::Escape) {
+        overlay.open = false;
+    }
+}
+
+/// The ? button, first visit hint and the overlay itself
+fn help_ui(
+    mut contexts: EguiContexts,
+    actions: Res<InputActions>,
+    toggles: Res<FeatureToggles>,
+    mut overlay: ResMut<HelpOverlay>,
+    mut hint: ResMut<HelpHint>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+
+    // Always there button for touch screens and anyone who doesn't try ?
+    egui::Area::new(egui::Id::new("help_button"))
+        .anchor(egui::Align2::LEFT_BOTTOM, egui::vec2(10.0, -10.0))
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button(" ? ").on_hover_text("Controls").clicked() {
+                    overlay.open = !overlay.open;
+                    hint.dismissed = true;
+                }
+
+                if !hint.dismissed {
+                    // The gestures would read oddly next to tapping the button
+                    let keys: Vec<String> = actions
+                        .bindings("help")
+                        .iter()
+                        .filter(|b| matches!(b, Binding::Key(_)))
+                        .map(|b| b.to_string())
+                        .collect();
+                    ui.label(match keys.as_slice() {
+                        [] => "Tap ? for controls".to_string(),
+                        keys => format!("Press {} or tap ? for controls", keys.join(", ")),
+                    });
+                    if ui.small_button("x").clicked() {
+                        hint.dismissed = true;
+                    }
+                }
+            });
+        });
+
+    if !overlay.open {
+        return Ok(());
+    }
+
+    let mut open = overlay.open;
+    egui::Window::new("Controls Help")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            egui::Grid::new("help").striped(true).show(ui, |ui| {
+                for line in help_lines(&actions, &toggles) {
+                    ui.label(line.bindings);
+                    ui.label(line.label);
+                    match line.enabled {
+                        Some(true) => ui.label("on"),
+                        Some(false) => ui.label("off"),
+                        None => ui.label(""),
+                    };
+                    ui.end_row();
+                }
+            });
+            ui.label("Bindings can be changed in the Controls window of the debug panel.");
+        });
+    overlay.open = open;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toggle::FeatureToggle;
+
+    #[test]
+    fn test_help_lines_follow_registrations() {
+        struct Tv;
+
+        let mut app = App::new();
+        app.add_plugins((
+            FeatureToggle::<Tv>::new("tv", "TV Effect", KeyCode::KeyT),
+            HelpPlugin,
+        ));
+        app.world_mut()
+            .resource_mut::<InputActions>()
+            .bind("tv", Binding::Key(KeyCode::KeyY));
+
+        let lines = help_lines(
+            app.world().resource::<InputActions>(),
+            app.world().resource::<FeatureToggles>(),
+        );
+
+        assert_eq!(
+            lines,
+            vec![
+                HelpLine {
+                    label: "TV Effect",
+                    bindings: "t, y".to_string(),
+                    enabled: Some(true),
+                },
+                HelpLine {
+                    label: "Help",
+                    bindings: "?, two-finger-tap".to_string(),
+                    enabled: None,
+                },
+            ]
+        );
+    }
+}
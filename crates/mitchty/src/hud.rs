@@ -0,0 +1,216 @@
+use bevy::diagnostic::{
+    DiagnosticPath, DiagnosticsStore, EntityCountDiagnosticsPlugin,
+    SystemInformationDiagnosticsPlugin,
+};
+use bevy::prelude::*;
+use bevy::render::renderer::RenderAdapterInfo;
+use bevy::window::PrimaryWindow;
+
+use crate::FpsDisplay;
+use crate::input::{Binding, InputActions, register_action};
+use crate::stats::FrameTimes;
+use crate::toggle::FeatureToggles;
+
+/// How much the fps display shows while it's on
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HudDetail {
+    /// Just the frame stats
+    #[default]
+    Fps,
+    /// Frame stats plus cpu/memory, scene and render info, enough to tell
+    /// what someone's machine is up to from a screenshot
+    Full,
+}
+
+impl HudDetail {
+    /// Next step of off, fps, full, off given whether the display is on
+    pub fn cycle(self, shown: bool) -> (bool, Self) {
+        match (shown, self) {
+            (false, _) => (true, Self::Fps),
+            (true, Self::Fps) => (true, Self::Full),
+            (true, Self::Full) => (false, Self::Fps),
+        }
+    }
+}
+
+/// Marker component for the FPS text entity
+#[derive(Component)]
+struct FpsText;
+
+/// Plugin for the fps/performance hud, f shows/hides it and p cycles through
+/// off, fps and the full hud
+pub struct PerfHudPlugin;
+
+impl Plugin for PerfHudPlugin {
+    fn build(&self, app: &mut App) {
+        register_action(
+            app,
+            "hud",
+            "Cycle Performance HUD",
+            vec![Binding::Key(KeyCode::KeyP)],
+        );
+
+        if !app.is_plugin_added::<EntityCountDiagnosticsPlugin>() {
+            app.add_plugins(EntityCountDiagnosticsPlugin::default());
+        }
+        // sysinfo has nothing to report in the browser, it just warns about it
+        #[cfg(not(target_arch = "wasm32"))]
+        if !app.is_plugin_added::<SystemInformationDiagnosticsPlugin>() {
+            app.add_plugins(SystemInformationDiagnosticsPlugin);
+        }
+
+        app.init_resource::<HudDetail>()
+            .add_systems(Startup, setup_fps_ui)
+            .add_systems(
+                Update,
+                (
+                    cycle_hud,
+                    update_fps_display.run_if(bevy::time::common_conditions::on_real_timer(
+                        std::time::Duration::from_secs_f32(0.5),
+                    )),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn cycle_hud(
+    actions: Res<InputActions>,
+    mut toggles: ResMut<FeatureToggles>,
+    mut detail: ResMut<HudDetail>,
+) {
+    if actions.just_triggered("hud") {
+        let (shown, next) = detail.cycle(toggles.is_enabled::<FpsDisplay>());
+        toggles.set("fps", shown);
+        *detail = next;
+    }
+}
+
+/// System to spawn the fps text entity
+fn setup_fps_ui(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.0, 1.0, 0.0)),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        },
+        FpsText,
+    ));
+}
+
+/// Smoothed latest value of a diagnostic, if it's registered and has one yet
+fn latest(diagnostics: &DiagnosticsStore, path: &DiagnosticPath) -> Option<f64> {
+    diagnostics.get(path).and_then(|d| d.smoothed())
+}
+
+/// System to update fps display when toggled, the stats come off real time
+/// so pausing or slowing the scene down doesn't mess with them
+#[allow(clippy::too_many_arguments)]
+fn update_fps_display(
+    frame_times: Res<FrameTimes>,
+    diagnostics: Res<DiagnosticsStore>,
+    detail: Res<HudDetail>,
+    toggles: Res<FeatureToggles>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    meshes: Res<Assets<Mesh>>,
+    materials: Res<Assets<StandardMaterial>>,
+    adapter: Option<Res<RenderAdapterInfo>>,
+    mut fps_text_query: Query<&mut Text, With<FpsText>>,
+) {
+    // Only update if the fps display is on
+    if !toggles.is_enabled::<FpsDisplay>() {
+        // Clear the text when FPS display is off
+        for mut text in fps_text_query.iter_mut() {
+            if !text.0.is_empty() {
+                text.0.clear();
+            }
+        }
+        return;
+    }
+    let Some(stats) = frame_times.stats else {
+        return;
+    };
+
+    let mut lines = vec![
+        format!("{:.1} fps {:.1} ms", stats.fps, stats.frame_ms),
+        format!("1% low {:.1} fps", stats.low_fps),
+        format!("max {:.1} ms", stats.max_ms),
+    ];
+
+    if *detail == HudDetail::Full {
+        let usage = |process, system| match (
+            latest(&diagnostics, &process),
+            latest(&diagnostics, &system),
+        ) {
+            (Some(process), Some(system)) => Some((process, system)),
+            _ => None,
+        };
+        lines.push(
+            match usage(
+                SystemInformationDiagnosticsPlugin::PROCESS_CPU_USAGE,
+                SystemInformationDiagnosticsPlugin::SYSTEM_CPU_USAGE,
+            ) {
+                Some((process, system)) => format!("cpu {process:.0}% (system {system:.0}%)"),
+                None => "cpu n/a".to_string(),
+            },
+        );
+        lines.push(
+            match usage(
+                SystemInformationDiagnosticsPlugin::PROCESS_MEM_USAGE,
+                SystemInformationDiagnosticsPlugin::SYSTEM_MEM_USAGE,
+            ) {
+                Some((process, system)) => {
+                    format!("mem {process:.2} GiB (system {system:.0}%)")
+                }
+                None => "mem n/a".to_string(),
+            },
+        );
+
+        if let Some(entities) = latest(&diagnostics, &EntityCountDiagnosticsPlugin::ENTITY_COUNT) {
+            lines.push(format!("{entities:.0} entities"));
+        }
+        lines.push(format!(
+            "{} meshes {} materials",
+            meshes.len(),
+            materials.len()
+        ));
+
+        if let Ok(window) = windows.single() {
+            lines.push(format!(
+                "{}x{} @ {:.2}x",
+                window.physical_width(),
+                window.physical_height(),
+                window.scale_factor()
+            ));
+        }
+        if let Some(adapter) = adapter {
+            lines.push(format!("{} ({:?})", adapter.name, adapter.backend));
+        }
+    }
+
+    for mut text in fps_text_query.iter_mut() {
+        text.0 = lines.join("\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_goes_off_fps_full_off() {
+        let (shown, detail) = HudDetail::Full.cycle(false);
+        assert_eq!((shown, detail), (true, HudDetail::Fps));
+        let (shown, detail) = detail.cycle(shown);
+        assert_eq!((shown, detail), (true, HudDetail::Full));
+        let (shown, detail) = detail.cycle(shown);
+        assert_eq!((shown, detail), (false, HudDetail::Fps));
+    }
+}
@@ -0,0 +1,425 @@
+use std::fmt;
+
+use bevy::input::InputSystems;
+use bevy::prelude::*;
+use bevy::reflect::{DynamicEnum, Typed, VariantInfo};
+use bevy_egui::input::EguiWantsInput;
+use serde::{Deserialize, Serialize};
+
+use crate::gesture::{GesturePlugin, TouchGestures, recognize_gestures};
+
+/// Touch gestures that can trigger an action
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Gesture {
+    /// Quick single finger touch
+    Tap,
+    DoubleTap,
+    /// Single finger held in place
+    LongPress,
+    /// Quick touch with two fingers at once
+    TwoFingerTap,
+}
+
+impl Gesture {
+    /// Every gesture, for listing in the controls ui
+    pub const ALL: [Gesture; 4] = [
+        Gesture::Tap,
+        Gesture::DoubleTap,
+        Gesture::LongPress,
+        Gesture::TwoFingerTap,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Gesture::Tap => "tap",
+            Gesture::DoubleTap => "double-tap",
+            Gesture::LongPress => "long-press",
+            Gesture::TwoFingerTap => "two-finger-tap",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|g| g.name() == name)
+    }
+}
+
+/// Something physical that can trigger an action. Serialized as a short
+/// string like key:KeyT pad:South or touch:tap so saved settings stay readable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Binding {
+    Key(KeyCode),
+    Gamepad(GamepadButton),
+    Touch(Gesture),
+}
+
+/// Build a unit enum variant from its name via reflection, saves keeping a
+/// giant table of every KeyCode around. Checks the name first as
+/// from_reflect panics on a variant that doesn't exist.
+fn unit_variant<T: FromReflect + Typed>(name: &str) -> Option<T> {
+    let info = T::type_info().as_enum().ok()?;
+    matches!(info.variant(name)?, VariantInfo::Unit(_))
+        .then(|| T::from_reflect(&DynamicEnum::new(name, ())))?
+}
+
+impl From<Binding> for String {
+    fn from(binding: Binding) -> Self {
+        match binding {
+            Binding::Key(key) => format!("key:{key:?}"),
+            Binding::Gamepad(button) => format!("pad:{button:?}"),
+            Binding::Touch(gesture) => format!("touch:{}", gesture.name()),
+        }
+    }
+}
+
+impl TryFrom<String> for Binding {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let parsed = match value.split_once(':') {
+            Some(("key", name)) => unit_variant::<KeyCode>(name).map(Binding::Key),
+            Some(("pad", name)) => unit_variant::<GamepadButton>(name).map(Binding::Gamepad),
+            Some(("touch", name)) => Gesture::from_name(name).map(Binding::Touch),
+            _ => None,
+        };
+        parsed.ok_or_else(|| format!("unknown binding {value}"))
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // What everyone calls it shifted, and what the help says to press
+            Binding::Key(KeyCode::Slash) => write!(f, "?"),
+            Binding::Key(key) => {
+                let name = format!("{key:?}");
+                let short = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .unwrap_or(&name);
+                write!(f, "{}", short.to_lowercase())
+            }
+            Binding::Gamepad(button) => write!(f, "pad {button:?}"),
+            Binding::Touch(gesture) => write!(f, "{}", gesture.name()),
+        }
+    }
+}
+
+/// A named action and what triggers it
+#[derive(Clone, Debug)]
+pub struct ActionEntry {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub bindings: Vec<Binding>,
+    pub defaults: Vec<Binding>,
+}
+
+/// Every named action and which ones fired this frame
+#[derive(Resource, Default)]
+pub struct InputActions {
+    entries: Vec<ActionEntry>,
+    triggered: Vec<&'static str>,
+    /// Set while the controls ui is waiting for a new binding so the press
+    /// doesn't also trigger whatever it is currently bound to
+    pub capturing: bool,
+}
+
+impl InputActions {
+    pub fn entries(&self) -> &[ActionEntry] {
+        &self.entries
+    }
+
+    /// Did the action fire this frame
+    pub fn just_triggered(&self, id: &str) -> bool {
+        self.triggered.contains(&id)
+    }
+
+    /// Current bindings of an action
+    pub fn bindings(&self, id: &str) -> &[Binding] {
+        self.entries
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.bindings.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Register an action, re-registering an id just replaces its defaults
+    pub fn register(&mut self, id: &'static str, label: &'static str, defaults: Vec<Binding>) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+            entry.label = label;
+            entry.bindings = defaults.clone();
+            entry.defaults = defaults;
+        } else {
+            self.entries.push(ActionEntry {
+                id,
+                label,
+                bindings: defaults.clone(),
+                defaults,
+            });
+        }
+    }
+
+    /// Action other than `id` already using this binding if any
+    pub fn conflict(&self, id: &str, binding: Binding) -> Option<&ActionEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.id != id && e.bindings.contains(&binding))
+    }
+
+    /// Add a binding to an action, stealing it from whatever else had it
+    pub fn bind(&mut self, id: &str, binding: Binding) {
+        for entry in &mut self.entries {
+            if entry.id == id {
+                if !entry.bindings.contains(&binding) {
+                    entry.bindings.push(binding);
+                }
+            } else {
+                entry.bindings.retain(|b| *b != binding);
+            }
+        }
+    }
+
+    pub fn unbind(&mut self, id: &str, binding: Binding) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+            entry.bindings.retain(|b| *b != binding);
+        }
+    }
+
+    /// Replace all bindings of an action, used when restoring saved bindings
+    pub fn set_bindings(&mut self, id: &str, bindings: Vec<Binding>) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+            entry.bindings = bindings;
+        }
+    }
+
+    /// Only the actions whose bindings differ from their defaults
+    pub fn customized(&self) -> impl Iterator<Item = &ActionEntry> {
+        self.entries.iter().filter(|e| e.bindings != e.defaults)
+    }
+
+    pub fn reset(&mut self) {
+        for entry in &mut self.entries {
+            entry.bindings = entry.defaults.clone();
+        }
+    }
+}
+
+/// Plugin that turns raw keyboard/gamepad/touch input into named actions
+pub struct InputActionPlugin;
+
+impl Plugin for InputActionPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<GesturePlugin>() {
+            app.add_plugins(GesturePlugin);
+        }
+
+        app.init_resource::<InputActions>().add_systems(
+            PreUpdate,
+            update_actions.after(InputSystems).after(recognize_gestures),
+        );
+    }
+}
+
+/// Register a named action, adding the action plugin first if need be
+pub fn register_action(
+    app: &mut App,
+    id: &'static str,
+    label: &'static str,
+    defaults: Vec<Binding>,
+) {
+    if !app.is_plugin_added::<InputActionPlugin>() {
+        app.add_plugins(InputActionPlugin);
+    }
+    app.world_mut()
+        .resource_mut::<InputActions>()
+        .register(id, label, defaults);
+}
+
+/// Did this binding fire this frame, `keyboard` is None while the keys are
+/// going somewhere else like an egui text field
+fn binding_fired(
+    binding: Binding,
+    keyboard: Option<&ButtonInput<KeyCode>>,
+    gamepads: &Query<&Gamepad>,
+    gestures: &TouchGestures,
+) -> bool {
+    match binding {
+        Binding::Key(key) => keyboard.is_some_and(|k| k.just_pressed(key)),
+        Binding::Gamepad(button) => gamepads.iter().any(|g| g.just_pressed(button)),
+        Binding::Touch(gesture) => gestures.fired.contains(&gesture),
+    }
+}
+
+/// Work out which actions fired this frame
+fn update_actions(
+    mut actions: ResMut<InputActions>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    gestures: Res<TouchGestures>,
+    egui_input: Option<Res<EguiWantsInput>>,
+) {
+    // Don't trip change detection every frame for nothing
+    if actions.triggered.is_empty() && actions.capturing {
+        return;
+    }
+
+    // Typing a t into a text field shouldn't toggle the tv
+    let typing = egui_input.is_some_and(|e| e.wants_any_keyboard_input());
+    let keyboard = (!typing).then_some(&*keyboard);
+
+    let triggered: Vec<&'static str> = if actions.capturing {
+        Vec::new()
+    } else {
+        actions
+            .entries
+            .iter()
+            .filter(|e| {
+                e.bindings
+                    .iter()
+                    .any(|b| binding_fired(*b, keyboard, &gamepads, &gestures))
+            })
+            .map(|e| e.id)
+            .collect()
+    };
+
+    if triggered != actions.triggered {
+        actions.triggered = triggered;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actions() -> InputActions {
+        let mut actions = InputActions::default();
+        actions.register("tv", "TV", vec![Binding::Key(KeyCode::KeyT)]);
+        actions.register(
+            "debug",
+            "Debug",
+            vec![Binding::Key(KeyCode::KeyD), Binding::Touch(Gesture::Tap)],
+        );
+        actions
+    }
+
+    #[test]
+    fn test_binding_string_roundtrip() {
+        for binding in [
+            Binding::Key(KeyCode::KeyT),
+            Binding::Key(KeyCode::Digit1),
+            Binding::Key(KeyCode::Slash),
+            Binding::Gamepad(GamepadButton::South),
+            Binding::Gamepad(GamepadButton::DPadUp),
+            Binding::Touch(Gesture::Tap),
+            Binding::Touch(Gesture::TwoFingerTap),
+        ] {
+            let text = String::from(binding);
+            assert_eq!(Binding::try_from(text), Ok(binding));
+        }
+    }
+
+    #[test]
+    fn test_binding_parse_garbage() {
+        assert!(Binding::try_from("key:NotAKey".to_string()).is_err());
+        assert!(Binding::try_from("mouse:Left".to_string()).is_err());
+        assert!(Binding::try_from("KeyT".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_binding_display() {
+        assert_eq!(Binding::Key(KeyCode::KeyT).to_string(), "t");
+        assert_eq!(Binding::Key(KeyCode::Digit4).to_string(), "4");
+        assert_eq!(Binding::Key(KeyCode::Space).to_string(), "space");
+        assert_eq!(Binding::Key(KeyCode::Slash).to_string(), "?");
+        assert_eq!(Binding::Touch(Gesture::Tap).to_string(), "tap");
+    }
+
+    #[test]
+    fn test_conflict_detection() {
+        let actions = actions();
+        let conflict = actions.conflict("tv", Binding::Key(KeyCode::KeyD));
+        assert_eq!(conflict.map(|e| e.id), Some("debug"));
+        assert!(
+            actions
+                .conflict("debug", Binding::Key(KeyCode::KeyD))
+                .is_none()
+        );
+        assert!(
+            actions
+                .conflict("tv", Binding::Key(KeyCode::KeyQ))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_bind_steals_and_reset_restores() {
+        let mut actions = actions();
+        actions.bind("tv", Binding::Key(KeyCode::KeyD));
+
+        assert_eq!(
+            actions.bindings("tv"),
+            &[Binding::Key(KeyCode::KeyT), Binding::Key(KeyCode::KeyD)]
+        );
+        assert_eq!(actions.bindings("debug"), &[Binding::Touch(Gesture::Tap)]);
+        assert_eq!(actions.customized().count(), 2);
+
+        actions.reset();
+        assert_eq!(actions.customized().count(), 0);
+    }
+
+    #[test]
+    fn test_keyboard_triggers_action() {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<Touches>()
+            .init_resource::<Time<Real>>()
+            .add_plugins(InputActionPlugin);
+        app.world_mut().resource_mut::<InputActions>().register(
+            "tv",
+            "TV",
+            vec![Binding::Key(KeyCode::KeyT)],
+        );
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyT);
+        app.update();
+        assert!(app.world().resource::<InputActions>().just_triggered("tv"));
+
+        // Capturing a rebind swallows the press
+        app.world_mut().resource_mut::<InputActions>().capturing = true;
+        app.update();
+        assert!(!app.world().resource::<InputActions>().just_triggered("tv"));
+    }
+
+    #[test]
+    fn test_keys_ignored_while_egui_has_keyboard() {
+        use bevy::ecs::system::RunSystemOnce;
+
+        let mut world = World::new();
+        world.init_resource::<TouchGestures>();
+        let mut keyboard = ButtonInput::<KeyCode>::default();
+        keyboard.press(KeyCode::KeyT);
+        world.insert_resource(keyboard);
+
+        let fired = world
+            .run_system_once(
+                |keyboard: Res<ButtonInput<KeyCode>>,
+                 gamepads: Query<&Gamepad>,
+                 mut gestures: ResMut<TouchGestures>| {
+                    gestures.fired.push(Gesture::Tap);
+                    let key = Binding::Key(KeyCode::KeyT);
+                    let tap = Binding::Touch(Gesture::Tap);
+                    [
+                        binding_fired(key, Some(&keyboard), &gamepads, &gestures),
+                        // egui wants the keyboard, the t is going into a text field
+                        binding_fired(key, None, &gamepads, &gestures),
+                        // Touch still works while typing
+                        binding_fired(tap, None, &gamepads, &gestures),
+                    ]
+                },
+            )
+            .unwrap();
+        assert_eq!(fired, [true, false, true]);
+    }
+}
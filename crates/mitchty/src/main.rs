@@ -1,463 +1,296 @@
 mod assets;
+mod camera;
+// Only the native build has a command line, the wasm build gets these from url
+// parameters instead
+#[cfg(not(target_arch = "wasm32"))]
+mod cli;
+mod clock;
+mod crt;
+mod formation;
+mod gamepad;
+mod gesture;
+mod help;
+mod hud;
+mod input;
+mod motion;
+mod pacing;
+mod palette;
+mod persist;
+mod power;
+mod quality;
+mod share;
+mod stats;
+mod toggle;
+mod tuning;
 mod ui;
 
 use assets::{AssetConfigPlugin, asset_path};
 use bevy::prelude::*;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
-use bevy_egui::EguiPlugin;
+use bevy_egui::{EguiGlobalSettings, EguiPlugin};
 use bevy_old_tv_shader::prelude::*;
-use ui::{SettingsUiPlugin, TvEffectEnabled};
-
-/// Absolute rotation speed
-const SPEED: f32 = 2.25;
-/// Minimum rotation speed in radians per second
-const MIN_SPEED: f32 = -SPEED;
-/// Maximum rotation speed in radians per second
-const MAX_SPEED: f32 = SPEED;
-/// Golden angle for rotation calculations
+use camera::{OrbitCameraPlugin, OrbitControl, RotatingCamera};
+#[cfg(not(target_arch = "wasm32"))]
+use cli::CliPlugin;
+use clock::ClockPlugin;
+use crt::{CrtParams, CrtPresetPlugin};
+use formation::FormationPlugin;
+use gamepad::GamepadControlPlugin;
+use help::HelpPlugin;
+use hud::PerfHudPlugin;
+use input::Binding;
+use motion::{Rotator, rotate_entities};
+use pacing::PacingPlugin;
+use palette::PalettePlugin;
+use persist::PersistPlugin;
+use power::PowerPlugin;
+use quality::{Quality, QualityPlugin};
+use share::ShareLinkPlugin;
+use stats::FrameStatsPlugin;
+use toggle::{Enabled, FeatureToggle, FeatureToggles};
+use tuning::{SceneTuning, TuningPlugin};
+use ui::SettingsUiPlugin;
+
+/// Golden angle for rotation calculations, SceneTuning's default
 const GOLDEN_ANGLE: f32 = 137.507_77;
 
-/// Marker component for entities that should rotate
-#[derive(Component)]
-struct Rotator {
-    /// Base rotation speed in radians per second for each axis (x, y, z)
-    base_speed: Vec3,
-}
+/// Multiplier on every cube's rotation speed, gamepad triggers drive this
+#[derive(Resource, PartialEq)]
+pub struct CubeSpeedScale(pub f32);
 
-/// Marker component to indicate cube rotation is enabled (on cube entities)
-#[derive(Component)]
-pub struct CubeRotationEnabled;
+impl Default for CubeSpeedScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
 
-/// Marker component separate from cube entities to control rotation
-#[derive(Component)]
+/// Feature marker for cube rotation
 pub struct CubeRotation;
 
-/// Marker component to indicate hue animation is enabled (on cube entities)
-#[derive(Component)]
-pub struct HueAnimationEnabled;
+/// On cube entities while cube rotation is enabled
+pub type CubeRotationEnabled = Enabled<CubeRotation>;
 
-/// Marker component separate from cube entities to control hue animation
-#[derive(Component)]
+/// Feature marker for cube hue animation
 pub struct HueAnimation;
 
-/// Marker component for the FPS text entity
-#[derive(Component)]
-struct FpsText;
+/// On cube entities while hue animation is enabled
+pub type HueAnimationEnabled = Enabled<HueAnimation>;
 
-/// Marker component to indicate FPS should be displayed and updated
-#[derive(Component)]
+/// Feature marker for camera rotation
+pub struct CameraRotation;
+
+/// On the camera while camera rotation is enabled
+pub type CameraRotationEnabled = Enabled<CameraRotation>;
+
+/// Feature marker for the fps display
 pub struct FpsDisplay;
 
-/// Marker component to indicate camera should be rotating
-#[derive(Component)]
-pub struct CameraRotationEnabled;
+/// Feature marker for the crt shader on the main camera
+pub struct TvEffect;
 
 /// Marker component for the main camera to enable TV effect toggling
 #[derive(Component)]
 pub struct MainCamera;
 
-/// Resource for the tv shader settings, for future maybe to make it so I can
-/// change stuff in it dynamically.
+/// Resource for the tv shader settings, the debug ui edits this and
+/// apply_tv_effect pushes any changes onto the camera.
 #[derive(Resource)]
 pub struct TvSettingsResource {
     pub settings: OldTvSettings,
 }
 
-/// Marker component for camera rotation
-#[derive(Component)]
-struct RotatingCamera {
-    /// Rotation speed in radians per second (positive = clockwise when viewed from above)
-    speed: f32,
-    /// Radius of rotation around the center
-    radius: f32,
-    /// Center to rotate around
-    center: Vec3,
-    /// Current angle in radians
-    angle: f32,
-    /// Height of the camera
-    height: f32,
+/// Seed for the initial cube speeds, random unless one is passed in so a
+/// scene can be shared/reproduced.
+#[derive(Resource)]
+pub struct SceneSeed(pub u64);
+
+impl Default for SceneSeed {
+    fn default() -> Self {
+        Self(rand::rng().random())
+    }
 }
 
-fn main() {
+/// The one rng everything random in the scene draws from, seeded from
+/// SceneSeed in setup so the same seed gives the same scene.
+#[derive(Resource)]
+pub struct SceneRng(pub ChaCha8Rng);
+
+impl SceneRng {
+    pub fn new(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+fn main() -> AppExit {
     // Set up better panic messages for WASM for when this stuff seems to not
     // work or I manage to use a library that won't run on it without paying
     // attention... again.
     #[cfg(target_arch = "wasm32")]
     console_error_panic_hook::set_once();
 
-    App::new()
-        .add_plugins(assets::create_default_plugins())
+    // Parsed before anything's built, but only reported once the default
+    // plugins have logging going
+    #[cfg(not(target_arch = "wasm32"))]
+    let args = cli::parse_args(std::env::args().skip(1));
+
+    let mut app = App::new();
+    app.add_plugins(assets::create_default_plugins())
         .add_plugins(AssetConfigPlugin)
         .add_plugins(OldTvPlugin)
         .add_plugins(EguiPlugin::default())
-        .add_plugins(SettingsUiPlugin)
-        .insert_resource(ClearColor(Color::WHITE))
-        .add_systems(Startup, (setup, setup_fps_ui))
+        // egui goes on the display camera QualityPlugin spawns, the scene
+        // camera might be rendering into an image
+        .insert_resource(EguiGlobalSettings {
+            auto_create_primary_context: false,
+            ..default()
+        })
+        .add_plugins((
+            FeatureToggle::<TvEffect>::new("tv", "TV Effect", KeyCode::KeyT)
+                .with_binding(Binding::Gamepad(GamepadButton::North)),
+            FeatureToggle::<FpsDisplay>::new("fps", "FPS Display", KeyCode::KeyF)
+                .with_binding(Binding::Gamepad(GamepadButton::Select))
+                .off_by_default(),
+            FeatureToggle::<CameraRotation>::new("camera", "Camera Rotation", KeyCode::KeyR)
+                .with_binding(Binding::Gamepad(GamepadButton::East))
+                .applies_to::<RotatingCamera>(),
+            FeatureToggle::<CubeRotation>::new("cube", "Cube Rotation", KeyCode::KeyC)
+                .with_binding(Binding::Gamepad(GamepadButton::South))
+                .applies_to::<Rotator>(),
+            FeatureToggle::<HueAnimation>::new("hue", "Hue Animation", KeyCode::KeyH)
+                .with_binding(Binding::Gamepad(GamepadButton::West))
+                .applies_to::<Rotator>(),
+        ))
+        .add_plugins(CrtPresetPlugin)
+        .add_plugins(PersistPlugin)
+        .add_plugins(ShareLinkPlugin)
+        .add_plugins(OrbitCameraPlugin)
+        .add_plugins(GamepadControlPlugin)
+        .add_plugins(ClockPlugin)
+        .add_plugins(TuningPlugin)
+        .add_plugins(FormationPlugin)
+        .add_plugins(PalettePlugin)
+        .add_plugins(FrameStatsPlugin)
+        .add_plugins(PerfHudPlugin)
+        .add_plugins(QualityPlugin)
+        .add_plugins(PowerPlugin)
+        .add_plugins(PacingPlugin)
+        .init_resource::<CubeSpeedScale>()
+        .init_resource::<SceneSeed>();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    match args {
+        Ok(args) if args.help => {
+            info!("{}", cli::USAGE);
+            return AppExit::Success;
+        }
+        Ok(args) => {
+            app.add_plugins(CliPlugin(args));
+        }
+        Err(e) => {
+            error!("{e}\n{}", cli::USAGE);
+            return AppExit::error();
+        }
+    }
+
+    app.add_plugins(SettingsUiPlugin)
+        .add_plugins(HelpPlugin)
+        .add_systems(Startup, setup)
         .add_systems(
             Update,
             (
-                animate_materials.run_if(any_with_component::<HueAnimationEnabled>),
                 rotate_entities.run_if(any_with_component::<CubeRotationEnabled>),
-                rotate_camera.run_if(any_with_component::<CameraRotationEnabled>),
-                toggle_fps_display,
-                toggle_camera_rotation,
-                toggle_cube_rotation,
-                toggle_hue_animation,
-                toggle_tv_effect,
-                apply_tv_effect,
-                apply_camera_rotation,
-                apply_cube_rotation,
-                apply_hue_animation,
-                update_fps_display.run_if(bevy::time::common_conditions::on_timer(
-                    std::time::Duration::from_secs_f32(0.5),
-                )),
+                apply_tv_effect.run_if(
+                    resource_changed::<FeatureToggles>
+                        .or(resource_changed::<TvSettingsResource>)
+                        .or(resource_changed::<Quality>),
+                ),
             ),
         )
-        .run();
+        .run()
+}
+
+/// The crt look the site ships with
+#[allow(clippy::field_reassign_with_default)]
+pub fn default_tv_settings() -> OldTvSettings {
+    let mut tv_settings = OldTvSettings::default();
+    tv_settings.screen_shape_factor = 0.2;
+    tv_settings.rows = 112.0;
+    tv_settings.brightness = 3.0;
+    tv_settings.edges_transition_size = 0.025;
+    tv_settings.channels_mask_min = 0.1;
+    tv_settings
 }
 
 fn setup(
     mut commands: Commands,
+    tv_settings: Res<TvSettingsResource>,
+    seed: Res<SceneSeed>,
+    tuning: Res<SceneTuning>,
     asset_server: Res<AssetServer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     let diffuse_path = asset_path("environment_maps/pisa_diffuse_rgb9e5_zstd.ktx2");
     let specular_path = asset_path("environment_maps/pisa_specular_rgb9e5_zstd.ktx2");
 
-    #[allow(clippy::field_reassign_with_default)]
-    let tv_settings = {
-        let mut tv_settings = OldTvSettings::default();
-        tv_settings.screen_shape_factor = 0.2;
-        tv_settings.rows = 112.0;
-        tv_settings.brightness = 3.0;
-        tv_settings.edges_transition_size = 0.025;
-        tv_settings.channels_mask_min = 0.1;
-        tv_settings
-    };
-
-    let initial_pos = Vec3::new(3.0, 1.0, 3.0);
-    let center = Vec3::new(0.0, -0.5, 0.0);
-
-    commands.insert_resource(TvSettingsResource {
-        settings: tv_settings,
-    });
+    let rotating = tuning.camera.rotating_camera();
 
     commands.spawn((
         Camera3d::default(),
-        tv_settings,
-        Transform::from_xyz(initial_pos.x, initial_pos.y, initial_pos.z)
-            .looking_at(center, Vec3::Y),
+        tv_settings.settings,
+        rotating.transform(),
         EnvironmentMapLight {
             diffuse_map: asset_server.load(diffuse_path),
             specular_map: asset_server.load(specular_path),
-            intensity: 2_000.0,
+            intensity: tuning.lighting.env_map_intensity,
             ..default()
         },
-        RotatingCamera {
-            speed: 0.3,
-            radius: (initial_pos.x.powi(2) + initial_pos.z.powi(2)).sqrt(),
-            center,
-            angle: initial_pos.z.atan2(initial_pos.x),
-            height: initial_pos.y,
-        },
-        CameraRotationEnabled,
+        rotating,
+        OrbitControl::default(),
         MainCamera,
     ));
 
-    let cube = meshes.add(Cuboid::new(0.5, 0.5, 0.5));
-
-    let mut hsla = Hsla::hsl(0.0, 1.0, 0.5);
-    let mut rng = rand::rng();
-
-    for x in -1..2 {
-        for z in -1..2 {
-            let base_speed = Vec3::new(
-                rng.random_range(MIN_SPEED..=MAX_SPEED),
-                rng.random_range(MIN_SPEED..=MAX_SPEED),
-                rng.random_range(MIN_SPEED..=MAX_SPEED),
-            );
-
-            commands.spawn((
-                Mesh3d(cube.clone()),
-                MeshMaterial3d(materials.add(Color::from(hsla))),
-                Transform::from_translation(Vec3::new(x as f32, 0.0, z as f32)),
-                Rotator { base_speed },
-                CubeRotationEnabled,
-                HueAnimationEnabled,
-            ));
-            hsla = hsla.rotate_hue(GOLDEN_ANGLE);
-        }
-    }
-}
-
-/// Cube material animation system
-fn animate_materials(
-    material_handles: Query<&MeshMaterial3d<StandardMaterial>, With<HueAnimationEnabled>>,
-    time: Res<Time>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-) {
-    for material_handle in material_handles.iter() {
-        if let Some(material) = materials.get_mut(material_handle)
-            && let Color::Hsla(ref mut hsla) = material.base_color
-        {
-            *hsla = hsla.rotate_hue(time.delta_secs() * 100.0);
-        }
-    }
-}
-
-/// Cube random rotation system
-fn rotate_entities(
-    mut query: Query<(&mut Transform, &mut Rotator), With<CubeRotationEnabled>>,
-    time: Res<Time>,
-) {
-    let mut rng = rand::rng();
-    let delta = time.delta_secs();
-
-    for (mut transform, mut rotator) in &mut query {
-        let change_x = rng.random_range(-0.1..=0.1);
-        let change_y = rng.random_range(-0.1..=0.1);
-        let change_z = rng.random_range(-0.1..=0.1);
-
-        rotator.base_speed.x += change_x;
-        rotator.base_speed.y += change_y;
-        rotator.base_speed.z += change_z;
-
-        if rotator.base_speed.x > MAX_SPEED {
-            rotator.base_speed.x = MAX_SPEED - (rotator.base_speed.x - MAX_SPEED);
-        }
-        if rotator.base_speed.y > MAX_SPEED {
-            rotator.base_speed.y = MAX_SPEED - (rotator.base_speed.y - MAX_SPEED);
-        }
-        if rotator.base_speed.z > MAX_SPEED {
-            rotator.base_speed.z = MAX_SPEED - (rotator.base_speed.z - MAX_SPEED);
-        }
-
-        if rotator.base_speed.x < MIN_SPEED {
-            rotator.base_speed.x = MIN_SPEED + (MIN_SPEED - rotator.base_speed.x);
-        }
-        if rotator.base_speed.y < MIN_SPEED {
-            rotator.base_speed.y = MIN_SPEED + (MIN_SPEED - rotator.base_speed.y);
-        }
-        if rotator.base_speed.z < MIN_SPEED {
-            rotator.base_speed.z = MIN_SPEED + (MIN_SPEED - rotator.base_speed.z);
-        }
-
-        transform.rotate_x(rotator.base_speed.x * delta);
-        transform.rotate_y(rotator.base_speed.y * delta);
-        transform.rotate_z(rotator.base_speed.z * delta);
-    }
-}
-
-/// Camera rotation state
-#[derive(Component)]
-pub struct CameraRotation;
-
-/// Rotation of the camera around the origin/center point
-fn rotate_camera(
-    time: Res<Time>,
-    mut query: Query<(&mut Transform, &mut RotatingCamera), With<CameraRotationEnabled>>,
-) {
-    for (mut transform, mut camera) in query.iter_mut() {
-        camera.angle += camera.speed * time.delta_secs();
-
-        let x = camera.center.x + camera.radius * camera.angle.cos();
-        let z = camera.center.z + camera.radius * camera.angle.sin();
-
-        transform.translation = Vec3::new(x, camera.height, z);
-
-        *transform = transform.looking_at(camera.center, Vec3::Y);
-    }
-}
-
-/// Toggle camera rotation
-/// r toggles on/off
-fn toggle_camera_rotation(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    rotation_query: Query<Entity, With<CameraRotation>>,
-    mut commands: Commands,
-) {
-    if keyboard.just_pressed(KeyCode::KeyR) {
-        if let Ok(entity) = rotation_query.single() {
-            commands.entity(entity).despawn();
-        } else {
-            commands.spawn(CameraRotation);
-        }
-    }
-}
-
-/// System to apply camera rotation.
-// TODO: rename toggle_*?
-fn apply_camera_rotation(
-    rotation_marker: Query<(), With<CameraRotation>>,
-    camera_query: Query<(Entity, Has<CameraRotationEnabled>), With<RotatingCamera>>,
-    mut commands: Commands,
-) {
-    let should_rotate = !rotation_marker.is_empty();
-
-    for (entity, has_rotation) in camera_query.iter() {
-        if should_rotate && !has_rotation {
-            commands.entity(entity).insert(CameraRotationEnabled);
-        } else if !should_rotate && has_rotation {
-            commands.entity(entity).remove::<CameraRotationEnabled>();
-        }
-    }
-}
-
-/// System to spawn the fps text entity
-fn setup_fps_ui(mut commands: Commands) {
-    commands.spawn((
-        Text::new(""),
-        TextFont {
-            font_size: 20.0,
-            ..default()
-        },
-        TextColor(Color::srgb(0.0, 1.0, 0.0)),
-        Node {
-            position_type: PositionType::Absolute,
-            top: Val::Px(10.0),
-            right: Val::Px(10.0),
-            ..default()
-        },
-        FpsText,
-    ));
-}
-
-/// Toggle FpsDisplay marker component to control systems that display the fps text
-/// f toggles on/off
-fn toggle_fps_display(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    fps_query: Query<Entity, With<FpsDisplay>>,
-    mut commands: Commands,
-) {
-    if keyboard.just_pressed(KeyCode::KeyF) {
-        if let Ok(entity) = fps_query.single() {
-            commands.entity(entity).despawn();
-        } else {
-            commands.spawn(FpsDisplay);
-        }
-    }
-}
-
-/// System to update fps display when toggled
-fn update_fps_display(
-    time: Res<Time>,
-    mut fps_text_query: Query<&mut Text, With<FpsText>>,
-    fps_display_query: Query<(), With<FpsDisplay>>,
-) {
-    // Only update if FpsDisplay marker exists
-    if fps_display_query.is_empty() {
-        // Clear the text when FPS display is off
-        for mut text in fps_text_query.iter_mut() {
-            if !text.0.is_empty() {
-                text.0.clear();
-            }
-        }
-    } else {
-        let fps = 1.0 / time.delta_secs();
-        for mut text in fps_text_query.iter_mut() {
-            text.0 = format!("{:.1} fps", fps);
-        }
-    }
-}
-
-/// Toggle TV effect on the main camera
-/// t toggles on/off
-fn toggle_tv_effect(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    tv_effect_query: Query<Entity, With<TvEffectEnabled>>,
-    mut commands: Commands,
-) {
-    if keyboard.just_pressed(KeyCode::KeyT) {
-        if let Ok(entity) = tv_effect_query.single() {
-            commands.entity(entity).despawn();
-        } else {
-            commands.spawn(TvEffectEnabled);
-        }
-    }
+    // Cubes get spawned by the formation once this is in
+    commands.insert_resource(SceneRng::new(seed.0));
 }
 
-/// Apply or remove TV effect toggle
+/// Fade the TV effect in/out with its toggle and copy any edited settings onto
+/// the camera, it only comes off the camera once fully faded out. Low quality
+/// levels take it off regardless of the toggle.
 fn apply_tv_effect(
-    tv_effect_query: Query<(), With<TvEffectEnabled>>,
-    camera_query: Query<(Entity, Has<OldTvSettings>), With<MainCamera>>,
+    toggles: Res<FeatureToggles>,
+    quality: Res<Quality>,
+    mut camera_query: Query<(Entity, &Camera, Option<&mut OldTvSettings>), With<MainCamera>>,
     tv_settings: Res<TvSettingsResource>,
     mut commands: Commands,
 ) {
-    let tv_should_be_enabled = !tv_effect_query.is_empty();
-
-    for (entity, has_tv_settings) in camera_query.iter() {
-        if tv_should_be_enabled && !has_tv_settings {
-            commands.entity(entity).insert(tv_settings.settings);
-        } else if !tv_should_be_enabled && has_tv_settings {
-            commands.entity(entity).remove::<OldTvSettings>();
-        }
-    }
-}
-
-/// Toggle cube rotation marker
-/// c toggles on/off
-fn toggle_cube_rotation(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    rotation_query: Query<Entity, With<CubeRotation>>,
-    mut commands: Commands,
-) {
-    if keyboard.just_pressed(KeyCode::KeyC) {
-        if let Ok(entity) = rotation_query.single() {
-            commands.entity(entity).despawn();
-        } else {
-            commands.spawn(CubeRotation);
-        }
-    }
-}
-
-/// Apply or remove CubeRotationEnabled component toggle
-fn apply_cube_rotation(
-    rotation_marker: Query<(), With<CubeRotation>>,
-    cube_query: Query<(Entity, Has<CubeRotationEnabled>), With<Rotator>>,
-    mut commands: Commands,
-) {
-    let should_rotate = !rotation_marker.is_empty();
-
-    for (entity, has_rotation) in cube_query.iter() {
-        if should_rotate && !has_rotation {
-            commands.entity(entity).insert(CubeRotationEnabled);
-        } else if !should_rotate && has_rotation {
-            commands.entity(entity).remove::<CubeRotationEnabled>();
-        }
-    }
-}
+    let intensity = if quality.current().tv {
+        toggles.intensity::<TvEffect>()
+    } else {
+        0.0
+    };
+    let full = CrtParams::from(tv_settings.settings);
 
-/// Toggle hue animation
-/// h toggles on/off
-fn toggle_hue_animation(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    hue_query: Query<Entity, With<HueAnimation>>,
-    mut commands: Commands,
-) {
-    if keyboard.just_pressed(KeyCode::KeyH) {
-        if let Ok(entity) = hue_query.single() {
-            commands.entity(entity).despawn();
+    for (entity, camera, current) in camera_query.iter_mut() {
+        let blended: OldTvSettings = if intensity >= 1.0 {
+            tv_settings.settings
         } else {
-            commands.spawn(HueAnimation);
-        }
-    }
-}
-
-/// Apply or remove HueAnimationEnabled component toggle
-fn apply_hue_animation(
-    hue_marker: Query<(), With<HueAnimation>>,
-    cube_query: Query<(Entity, Has<HueAnimationEnabled>), With<Rotator>>,
-    mut commands: Commands,
-) {
-    let should_animate = !hue_marker.is_empty();
-
-    for (entity, has_animation) in cube_query.iter() {
-        if should_animate && !has_animation {
-            commands.entity(entity).insert(HueAnimationEnabled);
-        } else if !should_animate && has_animation {
-            commands.entity(entity).remove::<HueAnimationEnabled>();
+            // Scanlines to match what the camera draws into, which is smaller
+            // than the window at lower render scales
+            let height = camera
+                .physical_target_size()
+                .map_or(full.rows, |size| size.y as f32);
+            CrtParams::neutral(height).lerp(full, intensity).into()
+        };
+        match current {
+            Some(_) if intensity <= 0.0 => {
+                commands.entity(entity).remove::<OldTvSettings>();
+            }
+            Some(mut settings) => *settings = blended,
+            None if intensity > 0.0 => {
+                commands.entity(entity).insert(blended);
+            }
+            None => {}
         }
     }
 }
@@ -0,0 +1,360 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::toggle::FeatureToggles;
+use crate::tuning::{CubeTuning, SceneTuning};
+use crate::{CubeRotation, CubeRotationEnabled, CubeSpeedScale, SceneRng};
+
+/// How fast the noise model drifts through its noise, in noise cells per second
+const NOISE_RATE: f32 = 0.4;
+/// Spring pull back towards a cube's starting speed, and how much it resists
+const SPRING_STIFFNESS: f32 = 6.0;
+const SPRING_DAMPING: f32 = 1.2;
+/// Random shoves keeping the spring wobbling, same units as CubeTuning::jitter
+const SPRING_KICK: f32 = 6.0;
+/// Radians per second of the wave, and phase offset per unit of grid distance
+const WAVE_RATE: f32 = 1.5;
+const WAVE_SPACING: f32 = 1.2;
+
+/// How a cube's angular velocity changes over time
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MotionModel {
+    /// Bounded random walk
+    #[default]
+    RandomWalk,
+    /// Smooth gradient (Perlin) noise, drifts around without the jitter
+    Noise,
+    /// Randomly shoved around a spring back to the starting speed
+    Spring,
+    /// Every cube spins back and forth together rippling out from the center
+    Wave,
+    /// Starting speed forever
+    Constant,
+}
+
+impl MotionModel {
+    /// Every model, for the ui
+    pub const ALL: [MotionModel; 5] = [
+        MotionModel::RandomWalk,
+        MotionModel::Noise,
+        MotionModel::Spring,
+        MotionModel::Wave,
+        MotionModel::Constant,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MotionModel::RandomWalk => "Random Walk",
+            MotionModel::Noise => "Noise",
+            MotionModel::Spring => "Spring Wobble",
+            MotionModel::Wave => "Wave",
+            MotionModel::Constant => "Constant",
+        }
+    }
+
+    /// Next angular velocity for a cube `elapsed` seconds in, `dt` after the last
+    pub fn step(
+        self,
+        rotator: &mut Rotator,
+        rng: &mut impl Rng,
+        elapsed: f32,
+        dt: f32,
+        tuning: &CubeTuning,
+    ) {
+        rotator.base_speed = match self {
+            MotionModel::RandomWalk => random_walk_step(rng, rotator.base_speed, dt, tuning),
+            MotionModel::Noise => {
+                let t = elapsed * NOISE_RATE + rotator.seed;
+                let (min, max) = tuning.speed_bounds();
+                // Far apart offsets so the axes don't move in lockstep
+                let noise = Vec3::new(
+                    gradient_noise(t),
+                    gradient_noise(t + 101.0),
+                    gradient_noise(t + 211.0),
+                );
+                min + (noise + 1.0) * 0.5 * (max - min)
+            }
+            MotionModel::Spring => {
+                let pull = (rotator.initial_speed - rotator.base_speed) * SPRING_STIFFNESS
+                    - rotator.spring_velocity * SPRING_DAMPING;
+                let kick = jitter(rng, SPRING_KICK, dt);
+                rotator.spring_velocity += pull * dt + kick;
+                clamp_speed(rotator.base_speed + rotator.spring_velocity * dt, tuning)
+            }
+            MotionModel::Wave => {
+                let t = elapsed * WAVE_RATE - rotator.phase;
+                let (min, max) = tuning.speed_bounds();
+                // Swings around the middle of the bounds, folded back in if
+                // tuning.speed takes it past them
+                let wave = Vec3::new(0.5 * t.cos(), t.sin(), 0.0) * tuning.speed;
+                clamp_speed(Vec3::splat((min + max) * 0.5) + wave, tuning)
+            }
+            MotionModel::Constant => rotator.initial_speed,
+        };
+    }
+}
+
+/// Spinning cube and whatever state its motion model needs
+#[derive(Component)]
+pub struct Rotator {
+    /// Base rotation speed in radians per second for each axis (x, y, z)
+    pub base_speed: Vec3,
+    /// Speed it was spawned with, constant/spring models go back to this
+    pub initial_speed: Vec3,
+    /// Wave phase, based on how far out it sits in the formation
+    pub phase: f32,
+    /// Where in the noise this cube starts so they don't all match
+    pub seed: f32,
+    /// Rate of change of base_speed for the spring model
+    spring_velocity: Vec3,
+}
+
+impl Rotator {
+    /// `position` is where it sits in the formation seen from above
+    pub fn new(base_speed: Vec3, position: Vec2, seed: f32) -> Self {
+        Self {
+            base_speed,
+            initial_speed: base_speed,
+            phase: Self::phase_at(position),
+            seed,
+            spring_velocity: Vec3::ZERO,
+        }
+    }
+
+    /// Wave phase for a cube sitting at `position` seen from above
+    pub fn phase_at(position: Vec2) -> f32 {
+        position.length() * WAVE_SPACING
+    }
+}
+
+/// Bounce a speed that went past min/max back inside by however far it
+/// overshot, a huge overshoot just gets clamped
+pub fn reflect_speed(speed: f32, min: f32, max: f32) -> f32 {
+    let reflected = if speed > max {
+        max - (speed - max)
+    } else if speed < min {
+        min + (min - speed)
+    } else {
+        speed
+    };
+    reflected.clamp(min, max)
+}
+
+fn clamp_speed(speed: Vec3, tuning: &CubeTuning) -> Vec3 {
+    let (min, max) = tuning.speed_bounds();
+    Vec3::new(
+        reflect_speed(speed.x, min, max),
+        reflect_speed(speed.y, min, max),
+        reflect_speed(speed.z, min, max),
+    )
+}
+
+/// Random change with variance amount² * dt per axis
+fn jitter(rng: &mut impl Rng, amount: f32, dt: f32) -> Vec3 {
+    // Uniform on ±sqrt(3) has unit variance
+    let scale = amount * dt.max(0.0).sqrt() * 3f32.sqrt();
+    Vec3::new(
+        rng.random_range(-scale..=scale),
+        rng.random_range(-scale..=scale),
+        rng.random_range(-scale..=scale),
+    )
+}
+
+/// One time step of the bounded random walk of a cube's angular velocity
+pub fn random_walk_step(rng: &mut impl Rng, speed: Vec3, dt: f32, tuning: &CubeTuning) -> Vec3 {
+    clamp_speed(speed + jitter(rng, tuning.jitter, dt), tuning)
+}
+
+/// Cheap integer hash to -1..1
+fn hash(n: i32) -> f32 {
+    let mut x = n as u32;
+    x = (x ^ 61) ^ (x >> 16);
+    x = x.wrapping_mul(9);
+    x ^= x >> 4;
+    x = x.wrapping_mul(0x27d4_eb2d);
+    x ^= x >> 15;
+    (x as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
+
+/// Smooth 1d gradient (Perlin) noise in -1..1, a random slope through zero at
+/// every whole number faded into the next
+pub fn gradient_noise(t: f32) -> f32 {
+    let cell = t.floor();
+    let f = t - cell;
+    let from = hash(cell as i32) * f;
+    let to = hash(cell as i32 + 1) * (f - 1.0);
+    let eased = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    // Slopes of ±1 only get to ±0.5 halfway between
+    (from.lerp(to, eased) * 2.0).clamp(-1.0, 1.0)
+}
+
+/// Step every spinning cube with its own model, easing in/out as cube rotation
+/// gets toggled
+pub fn rotate_entities(
+    mut query: Query<(&mut Transform, &mut Rotator, &MotionModel), With<CubeRotationEnabled>>,
+    time: Res<Time>,
+    scale: Res<CubeSpeedScale>,
+    toggles: Res<FeatureToggles>,
+    tuning: Res<SceneTuning>,
+    mut rng: ResMut<SceneRng>,
+) {
+    let delta = time.delta_secs();
+    let elapsed = time.elapsed_secs();
+    let scale = scale.0 * toggles.intensity::<CubeRotation>();
+
+    for (mut transform, mut rotator, model) in &mut query {
+        model.step(&mut rotator, &mut rng.0, elapsed, delta, &tuning.cubes);
+
+        let spin = rotator.base_speed * scale * delta;
+        transform.rotate_x(spin.x);
+        transform.rotate_y(spin.y);
+        transform.rotate_z(spin.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_reflect_speed() {
+        assert_eq!(reflect_speed(0.5, -2.0, 2.0), 0.5);
+        assert_eq!(reflect_speed(2.25, -2.0, 2.0), 1.75);
+        assert_eq!(reflect_speed(-2.25, -2.0, 2.0), -1.75);
+        // Too far out to bounce back in
+        assert_eq!(reflect_speed(10.0, -2.0, 2.0), -2.0);
+    }
+
+    /// Variance of where the walk ends up after a second of frames at some fps
+    fn spread_after_one_second(fps: u32) -> f32 {
+        let mut rng = StdRng::seed_from_u64(fps as u64);
+        let tuning = CubeTuning::default();
+        let walks = 4000;
+
+        let total: f32 = (0..walks)
+            .map(|_| {
+                let mut speed = Vec3::ZERO;
+                for _ in 0..fps {
+                    speed = random_walk_step(&mut rng, speed, 1.0 / fps as f32, &tuning);
+                }
+                speed.x * speed.x
+            })
+            .sum();
+
+        total / walks as f32
+    }
+
+    #[test]
+    fn test_walk_is_refresh_rate_independent() {
+        let at_60 = spread_after_one_second(60);
+        let at_144 = spread_after_one_second(144);
+
+        assert!((at_60 - at_144).abs() / at_60 < 0.1, "{at_60} vs {at_144}");
+
+        // And still looks like the old walk, ±0.1 every frame at 60fps
+        let mut rng = StdRng::seed_from_u64(1);
+        let walks = 4000;
+        let baseline = (0..walks)
+            .map(|_| {
+                let speed: f32 = (0..60).map(|_| rng.random_range(-0.1..=0.1)).sum();
+                speed * speed
+            })
+            .sum::<f32>()
+            / walks as f32;
+        assert!(
+            (at_60 - baseline).abs() / baseline < 0.1,
+            "{at_60} vs {baseline}"
+        );
+    }
+
+    #[test]
+    fn test_every_model_stays_in_bounds() {
+        let mut rng = StdRng::seed_from_u64(7);
+        // Defaults, and a wave far bigger than bounds that aren't around zero
+        let lopsided = CubeTuning {
+            speed: 8.0,
+            min_speed: 0.5,
+            max_speed: 3.0,
+            ..default()
+        };
+
+        for (model, tuning) in MotionModel::ALL
+            .into_iter()
+            .flat_map(|model| [(model, CubeTuning::default()), (model, lopsided.clone())])
+        {
+            let mut rotator = Rotator::new(Vec3::splat(tuning.max_speed), Vec2::ONE, 3.5);
+            for frame in 0..10_000 {
+                model.step(&mut rotator, &mut rng, frame as f32 * 0.1, 0.1, &tuning);
+                let speed = rotator.base_speed;
+                assert!(
+                    speed.cmple(Vec3::splat(tuning.max_speed)).all()
+                        && speed.cmpge(Vec3::splat(tuning.min_speed)).all(),
+                    "{model:?} went to {speed}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_motion() {
+        let run = |seed| {
+            let mut rng = SceneRng::new(seed);
+            let mut rotator = Rotator::new(Vec3::ZERO, Vec2::ONE, 1.0);
+            // Whole path, it ends on Constant which lands the same anyway
+            (0..600)
+                .map(|frame| {
+                    let model = MotionModel::ALL[frame / 120];
+                    model.step(
+                        &mut rotator,
+                        &mut rng.0,
+                        frame as f32 / 60.0,
+                        1.0 / 60.0,
+                        &CubeTuning::default(),
+                    );
+                    rotator.base_speed
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(42), run(42));
+        assert_ne!(run(42), run(43));
+    }
+
+    #[test]
+    fn test_constant_keeps_initial_speed() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut rotator = Rotator::new(Vec3::new(1.0, -0.5, 0.25), Vec2::ZERO, 0.0);
+        rotator.base_speed = Vec3::ZERO;
+        MotionModel::Constant.step(&mut rotator, &mut rng, 5.0, 0.016, &default());
+        assert_eq!(rotator.base_speed, Vec3::new(1.0, -0.5, 0.25));
+    }
+
+    #[test]
+    fn test_wave_is_in_sync_by_distance() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut a = Rotator::new(Vec3::ZERO, Vec2::new(1.0, 0.0), 0.0);
+        let mut b = Rotator::new(Vec3::ONE, Vec2::new(0.0, -1.0), 9.0);
+        let mut center = Rotator::new(Vec3::ZERO, Vec2::ZERO, 0.0);
+
+        for rotator in [&mut a, &mut b, &mut center] {
+            MotionModel::Wave.step(rotator, &mut rng, 2.0, 0.016, &default());
+        }
+        assert_eq!(a.base_speed, b.base_speed);
+        assert_ne!(a.base_speed, center.base_speed);
+    }
+
+    #[test]
+    fn test_noise_is_smooth() {
+        for i in 0..1000 {
+            let t = i as f32 * 0.013;
+            assert!((gradient_noise(t) - gradient_noise(t + 0.001)).abs() < 0.01);
+            assert!(gradient_noise(t).abs() <= 1.0);
+        }
+        // Gradient noise, not value noise, it's zero at every whole number
+        for n in -5..5 {
+            assert_eq!(gradient_noise(n as f32), 0.0);
+        }
+    }
+}
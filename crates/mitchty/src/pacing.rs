@@ -0,0 +1,298 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use bevy::prelude::*;
+#[cfg(not(any(target_arch = "wasm32", target_os = "macos")))]
+use bevy::render::{
+    Render, RenderApp,
+    renderer::{RenderAdapter, RenderInstance},
+    view::{ExtractedWindows, create_surfaces},
+};
+use bevy::window::{PresentMode, PrimaryWindow};
+use bevy::winit::UpdateMode;
+use serde::{Deserialize, Serialize};
+
+/// Most frames per second the app runs at
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameLimit {
+    Fps30,
+    Fps60,
+    Fps120,
+    #[default]
+    Uncapped,
+}
+
+impl FrameLimit {
+    pub const ALL: [FrameLimit; 4] = [
+        FrameLimit::Fps30,
+        FrameLimit::Fps60,
+        FrameLimit::Fps120,
+        FrameLimit::Uncapped,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FrameLimit::Fps30 => "30",
+            FrameLimit::Fps60 => "60",
+            FrameLimit::Fps120 => "120",
+            FrameLimit::Uncapped => "Uncapped",
+        }
+    }
+
+    pub fn fps(self) -> Option<f32> {
+        match self {
+            FrameLimit::Fps30 => Some(30.0),
+            FrameLimit::Fps60 => Some(60.0),
+            FrameLimit::Fps120 => Some(120.0),
+            FrameLimit::Uncapped => None,
+        }
+    }
+
+    /// Winit update mode holding to the limit, None runs flat out. Events
+    /// don't wake it early or moving the mouse would go over the cap, they
+    /// just wait for the next frame.
+    pub fn update_mode(self) -> Option<UpdateMode> {
+        self.fps().map(|fps| UpdateMode::Reactive {
+            wait: Duration::from_secs_f32(1.0 / fps),
+            react_to_device_events: false,
+            react_to_user_events: false,
+            react_to_window_events: false,
+        })
+    }
+}
+
+/// The present modes on offer, bevy's PresentMode isn't serializable without
+/// its serialize feature
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PresentChoice {
+    AutoVsync,
+    /// Bevy's default
+    #[default]
+    Fifo,
+    Mailbox,
+    Immediate,
+}
+
+impl PresentChoice {
+    pub const ALL: [PresentChoice; 4] = [
+        PresentChoice::AutoVsync,
+        PresentChoice::Fifo,
+        PresentChoice::Mailbox,
+        PresentChoice::Immediate,
+    ];
+
+    /// What the window's surface can do out of ALL. Fifo and Auto Vsync are
+    /// always there, wgpu panics on a Mailbox or Immediate the surface doesn't
+    /// have so those need to be in supported. Browsers always vsync.
+    pub fn available(supported: &[PresentMode]) -> Vec<PresentChoice> {
+        Self::ALL
+            .into_iter()
+            .filter(|choice| match choice {
+                PresentChoice::AutoVsync | PresentChoice::Fifo => true,
+                PresentChoice::Mailbox | PresentChoice::Immediate => {
+                    !cfg!(target_arch = "wasm32") && supported.contains(&choice.mode())
+                }
+            })
+            .collect()
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PresentChoice::AutoVsync => "Auto Vsync",
+            PresentChoice::Fifo => "Fifo (vsync)",
+            PresentChoice::Mailbox => "Mailbox (fast vsync)",
+            PresentChoice::Immediate => "Immediate (no vsync)",
+        }
+    }
+
+    fn mode(self) -> PresentMode {
+        match self {
+            PresentChoice::AutoVsync => PresentMode::AutoVsync,
+            PresentChoice::Fifo => PresentMode::Fifo,
+            PresentChoice::Mailbox => PresentMode::Mailbox,
+            PresentChoice::Immediate => PresentMode::Immediate,
+        }
+    }
+
+    /// Mode to give the window, anything the surface can't do is Fifo
+    pub fn present_mode(self, supported: &[PresentMode]) -> PresentMode {
+        if Self::available(supported).contains(&self) {
+            self.mode()
+        } else {
+            PresentMode::Fifo
+        }
+    }
+}
+
+/// Frame limit and present mode, saved with the other settings. The present
+/// mode stays what was picked even where it falls back to Fifo.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pacing {
+    pub limit: FrameLimit,
+    pub present: PresentChoice,
+}
+
+/// Present modes the primary window's surface has, the render world fills it
+/// in once the window is up and it's empty until then
+#[derive(Resource, Clone, Default)]
+pub struct SupportedPresentModes(Arc<Mutex<Vec<PresentMode>>>);
+
+impl SupportedPresentModes {
+    pub fn lock(&self) -> MutexGuard<'_, Vec<PresentMode>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Plugin putting the present mode on the window, the frame limit goes
+/// through the power saving update modes
+pub struct PacingPlugin;
+
+impl Plugin for PacingPlugin {
+    fn build(&self, app: &mut App) {
+        let supported = SupportedPresentModes::default();
+        // Metal has Immediate from macOS 10.13 on, and probing it would leave
+        // a spare layer on the window
+        #[cfg(target_os = "macos")]
+        supported
+            .lock()
+            .extend([PresentMode::Fifo, PresentMode::Immediate]);
+        #[cfg(not(any(target_arch = "wasm32", target_os = "macos")))]
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .insert_resource(supported.clone())
+                .add_systems(Render, probe_present_modes.before(create_surfaces));
+        }
+
+        app.init_resource::<Pacing>()
+            .insert_resource(supported)
+            .add_systems(Update, apply_present_mode);
+    }
+}
+
+/// Read the present modes off a throwaway surface for the primary window,
+/// once, before bevy makes the real one with whatever mode is picked
+#[cfg(not(any(target_arch = "wasm32", target_os = "macos")))]
+fn probe_present_modes(
+    windows: Res<ExtractedWindows>,
+    instance: Res<RenderInstance>,
+    adapter: Res<RenderAdapter>,
+    supported: Res<SupportedPresentModes>,
+    mut probed: Local<bool>,
+) {
+    let Some(window) = windows.primary.and_then(|entity| windows.get(&entity)) else {
+        return;
+    };
+    if std::mem::replace(&mut *probed, true) {
+        return;
+    }
+
+    let target = wgpu::SurfaceTargetUnsafe::RawHandle {
+        raw_display_handle: window.handle.get_display_handle(),
+        raw_window_handle: window.handle.get_window_handle(),
+    };
+    // SAFETY: the extracted window holds on to the window, so the handles
+    // outlive the surface which goes as soon as this returns
+    let surface = match unsafe { instance.create_surface_unsafe(target) } {
+        Ok(surface) => surface,
+        Err(e) => {
+            warn!("couldn't read the present modes, only offering vsync: {e}");
+            return;
+        }
+    };
+    let modes: Vec<_> = surface
+        .get_capabilities(&adapter)
+        .present_modes
+        .into_iter()
+        .map(|mode| match mode {
+            wgpu::PresentMode::AutoVsync => PresentMode::AutoVsync,
+            wgpu::PresentMode::AutoNoVsync => PresentMode::AutoNoVsync,
+            wgpu::PresentMode::Fifo => PresentMode::Fifo,
+            wgpu::PresentMode::FifoRelaxed => PresentMode::FifoRelaxed,
+            wgpu::PresentMode::Immediate => PresentMode::Immediate,
+            wgpu::PresentMode::Mailbox => PresentMode::Mailbox,
+        })
+        .collect();
+    debug!("present modes {:?}", modes);
+    *supported.lock() = modes;
+}
+
+fn apply_present_mode(
+    pacing: Res<Pacing>,
+    supported: Res<SupportedPresentModes>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut warned: Local<Option<PresentChoice>>,
+) {
+    let supported = supported.lock();
+    let mode = pacing.present.present_mode(&supported);
+    // Nothing to say until the modes are known, it's Fifo till then anyway
+    if mode != pacing.present.mode() && !supported.is_empty() && *warned != Some(pacing.present) {
+        warn!(
+            "{} isn't supported here, using Fifo",
+            pacing.present.label()
+        );
+        *warned = Some(pacing.present);
+    }
+    for mut window in windows.iter_mut() {
+        if window.present_mode != mode {
+            info!("present mode {:?}", mode);
+            window.present_mode = mode;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_waits_a_frame_and_ignores_events() {
+        assert_eq!(FrameLimit::Uncapped.update_mode(), None);
+        let Some(UpdateMode::Reactive {
+            wait,
+            react_to_device_events,
+            react_to_user_events,
+            react_to_window_events,
+        }) = FrameLimit::Fps30.update_mode()
+        else {
+            panic!("30fps should be reactive");
+        };
+        assert!((wait.as_secs_f32() - 1.0 / 30.0).abs() < 1e-6);
+        assert!(!(react_to_device_events || react_to_user_events || react_to_window_events));
+    }
+
+    #[test]
+    fn test_unsupported_modes_fall_back_to_fifo() {
+        // Before the surface is read, or where it only has Fifo
+        for supported in [&[][..], &[PresentMode::Fifo]] {
+            assert_eq!(
+                PresentChoice::available(supported),
+                [PresentChoice::AutoVsync, PresentChoice::Fifo]
+            );
+            assert_eq!(
+                PresentChoice::Immediate.present_mode(supported),
+                PresentMode::Fifo
+            );
+        }
+
+        let supported = [PresentMode::Fifo, PresentMode::Immediate];
+        assert_eq!(
+            PresentChoice::Mailbox.present_mode(&supported),
+            PresentMode::Fifo
+        );
+        if !cfg!(target_arch = "wasm32") {
+            assert_eq!(
+                PresentChoice::Immediate.present_mode(&supported),
+                PresentMode::Immediate
+            );
+        }
+    }
+
+    #[test]
+    fn test_present_choice_saves_as_picked() {
+        let pacing: Pacing = ron::from_str("(present: Mailbox)").unwrap();
+        assert_eq!(pacing.present, PresentChoice::Mailbox);
+        let text = ron::to_string(&pacing).unwrap();
+        assert_eq!(ron::from_str::<Pacing>(&text).unwrap(), pacing);
+    }
+}
@@ -0,0 +1,418 @@
+use bevy::prelude::*;
+
+use crate::assets::asset_path;
+use crate::formation::{Formation, FormationSlot, sync_formation};
+use crate::quality::Quality;
+use crate::toggle::FeatureToggles;
+use crate::tuning::{ColorTuning, SceneTuning};
+use crate::{HueAnimation, HueAnimationEnabled};
+
+/// Degrees either side of the middle cube's hue the analogous mode spreads over
+const ANALOGOUS_SPREAD: f32 = 30.0;
+
+/// Gradients the image mode can sample, label and asset path
+pub const GRADIENTS: [(&str, &str); 2] = [
+    ("Sunset", "palettes/sunset.png"),
+    ("Ocean", "palettes/ocean.png"),
+];
+
+/// How cube colors get picked
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PaletteMode {
+    /// Each cube the golden angle around from the last, the original look
+    #[default]
+    GoldenAngle,
+    /// Neighbouring hues spread evenly across the formation
+    Analogous,
+    /// Alternating between opposite hues
+    Complementary,
+    /// Three hues a third of the way round from each other
+    Triadic,
+    /// The user's own colors handed out in turn
+    Fixed,
+    /// Spread along a gradient image
+    Image,
+}
+
+impl PaletteMode {
+    pub const ALL: [PaletteMode; 6] = [
+        PaletteMode::GoldenAngle,
+        PaletteMode::Analogous,
+        PaletteMode::Complementary,
+        PaletteMode::Triadic,
+        PaletteMode::Fixed,
+        PaletteMode::Image,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PaletteMode::GoldenAngle => "Golden Angle",
+            PaletteMode::Analogous => "Analogous",
+            PaletteMode::Complementary => "Complementary",
+            PaletteMode::Triadic => "Triadic",
+            PaletteMode::Fixed => "Fixed Palette",
+            PaletteMode::Image => "Image Gradient",
+        }
+    }
+}
+
+/// Color space the palette is built and animated in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HueSpace {
+    /// Perceptually even, lightness stays put as the hue goes round
+    #[default]
+    Oklch,
+    /// The original look, yellow flashes bright and blue goes dark
+    Hsl,
+}
+
+impl HueSpace {
+    pub const ALL: [HueSpace; 2] = [HueSpace::Oklch, HueSpace::Hsl];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HueSpace::Oklch => "Oklch",
+            HueSpace::Hsl => "HSL (legacy)",
+        }
+    }
+}
+
+/// Oklch lightness and chroma the saturation/lightness sliders map onto at
+/// their defaults, roughly as bright as hsl at 50%. Yellows through cyans can't
+/// get that colorful at this lightness, fit_srgb tones those down.
+const OKLCH_LIGHTNESS: f32 = 0.7;
+const OKLCH_CHROMA: f32 = 0.15;
+
+/// Cut chroma back until the color fits in srgb. Letting the channels clip
+/// instead changes how bright it looks, undoing the point of oklch.
+fn fit_srgb(color: Oklcha) -> Oklcha {
+    let fits = |chroma| {
+        let rgb = LinearRgba::from(color.with_chroma(chroma));
+        [rgb.red, rgb.green, rgb.blue]
+            .iter()
+            .all(|c| (0.0..=1.0).contains(c))
+    };
+    if fits(color.chroma) {
+        return color;
+    }
+
+    let (mut inside, mut outside) = (0.0, color.chroma);
+    for _ in 0..16 {
+        let chroma = (inside + outside) * 0.5;
+        if fits(chroma) {
+            inside = chroma;
+        } else {
+            outside = chroma;
+        }
+    }
+    color.with_chroma(inside)
+}
+
+/// A cube's color before shading, either a hue from one of the harmonies or a
+/// color picked from somewhere else
+enum Swatch {
+    Hue(f32),
+    Color(Color),
+}
+
+/// Current cube colors, change it and the cubes get repainted
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub mode: PaletteMode,
+    pub space: HueSpace,
+    pub saturation: f32,
+    pub lightness: f32,
+    /// Colors for the fixed mode
+    pub fixed: Vec<Srgba>,
+    /// Which of GRADIENTS the image mode samples
+    pub gradient: usize,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            mode: PaletteMode::GoldenAngle,
+            space: HueSpace::Oklch,
+            saturation: 1.0,
+            lightness: 0.5,
+            fixed: vec![
+                Srgba::hex("e63946").unwrap(),
+                Srgba::hex("f1c453").unwrap(),
+                Srgba::hex("2a9d8f").unwrap(),
+                Srgba::hex("457b9d").unwrap(),
+            ],
+            gradient: 0,
+        }
+    }
+}
+
+impl Palette {
+    /// Color of the cube in `slot` of `count` with the hue animation `cycle`
+    /// degrees round. `gradient` is the image mode's image if it has loaded,
+    /// until then it falls back to the golden angle.
+    pub fn color(
+        &self,
+        slot: usize,
+        count: usize,
+        gradient: Option<&Image>,
+        cycle: f32,
+        tuning: &ColorTuning,
+    ) -> Color {
+        let default = Palette::default();
+        // Relative to the defaults so 1 leaves a color as it is
+        let saturation = self.saturation / default.saturation;
+        let lightness = self.lightness / default.lightness;
+
+        match (self.space, self.swatch(slot, count, gradient, tuning)) {
+            (HueSpace::Hsl, Swatch::Hue(hue)) => Hsla::hsl(hue, self.saturation, self.lightness)
+                .rotate_hue(cycle)
+                .into(),
+            (HueSpace::Hsl, Swatch::Color(color)) => {
+                let hsla = Hsla::from(color);
+                hsla.with_saturation((hsla.saturation * saturation).min(1.0))
+                    .with_lightness((hsla.lightness * lightness).min(1.0))
+                    .rotate_hue(cycle)
+                    .into()
+            }
+            (HueSpace::Oklch, Swatch::Hue(hue)) => fit_srgb(
+                Oklcha::lch(
+                    (OKLCH_LIGHTNESS * lightness).min(1.0),
+                    OKLCH_CHROMA * saturation,
+                    hue,
+                )
+                .rotate_hue(cycle),
+            )
+            .into(),
+            (HueSpace::Oklch, Swatch::Color(color)) => {
+                let oklcha = Oklcha::from(color);
+                fit_srgb(
+                    oklcha
+                        .with_chroma(oklcha.chroma * saturation)
+                        .with_lightness((oklcha.lightness * lightness).min(1.0))
+                        .rotate_hue(cycle),
+                )
+                .into()
+            }
+        }
+    }
+
+    fn swatch(
+        &self,
+        slot: usize,
+        count: usize,
+        gradient: Option<&Image>,
+        tuning: &ColorTuning,
+    ) -> Swatch {
+        let golden = Swatch::Hue((tuning.golden_angle * slot as f32).rem_euclid(360.0));
+        // How far through the formation this cube is
+        let t = if count > 1 {
+            slot as f32 / (count - 1) as f32
+        } else {
+            0.5
+        };
+
+        match self.mode {
+            PaletteMode::GoldenAngle => golden,
+            PaletteMode::Analogous => {
+                Swatch::Hue(((t * 2.0 - 1.0) * ANALOGOUS_SPREAD).rem_euclid(360.0))
+            }
+            PaletteMode::Complementary => Swatch::Hue(180.0 * (slot % 2) as f32),
+            PaletteMode::Triadic => Swatch::Hue(120.0 * (slot % 3) as f32),
+            PaletteMode::Fixed => match self.fixed.len() {
+                0 => golden,
+                n => Swatch::Color(self.fixed[slot % n].into()),
+            },
+            PaletteMode::Image => gradient
+                .and_then(|image| sample_gradient(image, t))
+                .map_or(golden, Swatch::Color),
+        }
+    }
+}
+
+/// Color `t` of the way across the middle row of an image
+fn sample_gradient(image: &Image, t: f32) -> Option<Color> {
+    let x = (t.clamp(0.0, 1.0) * (image.width().max(1) - 1) as f32).round() as u32;
+    image.get_color_at(x, image.height() / 2).ok()
+}
+
+/// How far round the hue animation has gone, in degrees
+#[derive(Resource, Default)]
+pub struct HueCycle(pub f32);
+
+/// Handles for GRADIENTS, same order
+#[derive(Resource, Default)]
+struct GradientImages(Vec<Handle<Image>>);
+
+/// Plugin coloring the cubes and animating their hue
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Palette>()
+            .init_resource::<HueCycle>()
+            .init_resource::<GradientImages>()
+            .add_systems(Startup, load_gradients)
+            .add_systems(
+                Update,
+                (
+                    cycle_hues.run_if(any_with_component::<HueAnimationEnabled>),
+                    paint_cubes,
+                )
+                    .chain()
+                    .after(sync_formation),
+            );
+    }
+}
+
+fn load_gradients(asset_server: Res<AssetServer>, mut gradients: ResMut<GradientImages>) {
+    gradients.0 = GRADIENTS
+        .iter()
+        .map(|(_, path)| asset_server.load(asset_path(path)))
+        .collect();
+}
+
+/// Hue animation system, easing in/out as it gets toggled
+fn cycle_hues(
+    time: Res<Time>,
+    tuning: Res<SceneTuning>,
+    toggles: Res<FeatureToggles>,
+    mut cycle: ResMut<HueCycle>,
+) {
+    let speed = tuning.colors.hue_speed * toggles.intensity::<HueAnimation>();
+    cycle.0 = (cycle.0 + time.delta_secs() * speed).rem_euclid(360.0);
+}
+
+/// Repaint every cube when the palette, tuning or hue changes, new cubes, or
+/// once the gradient being used finishes loading
+#[allow(clippy::too_many_arguments)]
+fn paint_cubes(
+    palette: Res<Palette>,
+    cycle: Res<HueCycle>,
+    tuning: Res<SceneTuning>,
+    formation: Res<Formation>,
+    quality: Res<Quality>,
+    gradients: Res<GradientImages>,
+    images: Res<Assets<Image>>,
+    mut image_events: MessageReader<AssetEvent<Image>>,
+    added: Query<(), Added<FormationSlot>>,
+    cubes: Query<(&FormationSlot, &MeshMaterial3d<StandardMaterial>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let gradient = gradients.0.get(palette.gradient);
+    let loaded = image_events
+        .read()
+        .any(|event| gradient.is_some_and(|handle| event.is_loaded_with_dependencies(handle)));
+
+    let changed =
+        palette.is_changed() || cycle.is_changed() || tuning.is_changed() || quality.is_changed();
+    if !(changed || loaded || !added.is_empty()) {
+        return;
+    }
+
+    let image = gradient.and_then(|handle| images.get(handle));
+    let count = formation.cube_count().min(quality.current().max_cubes);
+    for (slot, handle) in cubes.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = palette.color(slot.0, count, image, cycle.0, &tuning.colors);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette(mode: PaletteMode, space: HueSpace) -> Palette {
+        Palette {
+            mode,
+            space,
+            ..default()
+        }
+    }
+
+    fn hsl(mode: PaletteMode, slot: usize, count: usize) -> Hsla {
+        palette(mode, HueSpace::Hsl)
+            .color(slot, count, None, 0.0, &default())
+            .into()
+    }
+
+    #[test]
+    fn test_golden_angle_matches_original() {
+        let original = Hsla::hsl(0.0, 1.0, 0.5).rotate_hue(crate::GOLDEN_ANGLE * 4.0);
+        let color = hsl(PaletteMode::GoldenAngle, 4, 9);
+        assert!((color.hue - original.hue).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_harmonies() {
+        let hues = |mode| {
+            (0..4)
+                .map(|slot| hsl(mode, slot, 4).hue)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(hues(PaletteMode::Complementary), [0.0, 180.0, 0.0, 180.0]);
+        assert_eq!(hues(PaletteMode::Triadic), [0.0, 120.0, 240.0, 0.0]);
+
+        let analogous = hues(PaletteMode::Analogous);
+        assert_eq!(analogous[0], 360.0 - ANALOGOUS_SPREAD);
+        assert_eq!(analogous[3], ANALOGOUS_SPREAD);
+    }
+
+    #[test]
+    fn test_fixed_palette_cycles_and_shades() {
+        let mut fixed = palette(PaletteMode::Fixed, HueSpace::Hsl);
+        let first: Hsla = fixed.fixed[0].into();
+        let wrapped: Hsla = fixed
+            .color(fixed.fixed.len(), 20, None, 0.0, &default())
+            .into();
+        assert_eq!(wrapped, first);
+
+        fixed.lightness = 0.25;
+        let darker: Hsla = fixed.color(0, 20, None, 0.0, &default()).into();
+        assert!((darker.lightness - first.lightness * 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_image_falls_back_until_loaded() {
+        for space in HueSpace::ALL {
+            assert_eq!(
+                palette(PaletteMode::Image, space).color(3, 9, None, 0.0, &default()),
+                palette(PaletteMode::GoldenAngle, space).color(3, 9, None, 0.0, &default())
+            );
+        }
+    }
+
+    #[test]
+    fn test_oklch_keeps_lightness_as_hue_cycles() {
+        let lightness = |space, cycle| {
+            let color =
+                palette(PaletteMode::GoldenAngle, space).color(0, 9, None, cycle, &default());
+            Oklaba::from(color.to_srgba()).lightness
+        };
+
+        // Yellow against blue, hsl is all over the place
+        assert!((lightness(HueSpace::Hsl, 60.0) - lightness(HueSpace::Hsl, 240.0)).abs() > 0.3);
+
+        let start = lightness(HueSpace::Oklch, 0.0);
+        for cycle in (0..360).step_by(15) {
+            let now = lightness(HueSpace::Oklch, cycle as f32);
+            assert!((now - start).abs() < 1e-3, "{cycle}: {now} vs {start}");
+        }
+    }
+
+    #[test]
+    fn test_oklch_stays_inside_srgb() {
+        let oklch = palette(PaletteMode::GoldenAngle, HueSpace::Oklch);
+        for cycle in 0..360 {
+            let color = oklch.color(0, 9, None, cycle as f32, &default()).to_srgba();
+            for channel in [color.red, color.green, color.blue] {
+                assert!((0.0..=1.0).contains(&channel), "{cycle}: {color:?}");
+            }
+        }
+        // Cyan at full chroma doesn't fit, it gets toned down not clipped
+        let cyan = Oklcha::lch(OKLCH_LIGHTNESS, OKLCH_CHROMA, 200.0);
+        assert!(fit_srgb(cyan).chroma < cyan.chroma);
+        assert_eq!(fit_srgb(cyan).lightness, cyan.lightness);
+    }
+}
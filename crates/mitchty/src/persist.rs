@@ -0,0 +1,385 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use bevy::window::WindowCloseRequested;
+use serde::{Deserialize, Serialize};
+
+use crate::TvSettingsResource;
+use crate::crt::{CrtParams, CrtPreset, CrtPresets};
+use crate::help::HelpHint;
+use crate::input::{Binding, InputActions};
+use crate::pacing::Pacing;
+use crate::power::PowerSettings;
+use crate::share::UrlOverrides;
+use crate::toggle::FeatureToggles;
+
+/// localStorage key on wasm, also the directory name under the config dir on native
+const SETTINGS_KEY: &str = "mitchty";
+
+/// How often changed settings get written out, closing writes them straight away
+const SAVE_INTERVAL_SECS: f32 = 1.0;
+
+/// Everything that survives a reload. Missing fields fall back to the defaults
+/// so older saved settings still load after new things get added here.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedSettings {
+    /// Feature toggle id to state, anything missing keeps its default
+    pub toggles: BTreeMap<String, bool>,
+    /// Action id to bindings, only actions that were rebound
+    pub bindings: BTreeMap<String, Vec<Binding>>,
+    /// srgba
+    pub clear_color: [f32; 4],
+    pub crt: CrtParams,
+    pub crt_active: Option<String>,
+    pub crt_blend_secs: f32,
+    pub crt_presets: Vec<CrtPreset>,
+    /// Set once the first visit help hint has been closed
+    pub help_hint_dismissed: bool,
+    pub power: PowerSettings,
+    /// Frame limit and present mode
+    pub pacing: Pacing,
+}
+
+impl Default for SavedSettings {
+    fn default() -> Self {
+        let presets = CrtPresets::default();
+        Self {
+            toggles: BTreeMap::new(),
+            bindings: BTreeMap::new(),
+            clear_color: [1.0, 1.0, 1.0, 1.0],
+            crt: CrtParams::default(),
+            crt_active: presets.active,
+            crt_blend_secs: presets.blend_secs,
+            crt_presets: presets.user,
+            help_hint_dismissed: false,
+            power: PowerSettings::default(),
+            pacing: Pacing::default(),
+        }
+    }
+}
+
+impl SavedSettings {
+    pub fn clear_color(&self) -> Color {
+        let [r, g, b, a] = self.clear_color;
+        Color::srgba(r, g, b, a)
+    }
+}
+
+/// Changed settings not written out yet. Shared so the browser's pagehide
+/// listener can write them, there might not be another frame after it.
+#[derive(Resource, Clone, Default)]
+struct PendingSave(Arc<Mutex<Option<String>>>);
+
+impl PendingSave {
+    fn set(&self, text: String) {
+        if let Ok(mut pending) = self.0.lock() {
+            *pending = Some(text);
+        }
+    }
+
+    /// Write out whatever's pending
+    fn flush(&self) {
+        let Some(text) = self.0.lock().ok().and_then(|mut p| p.take()) else {
+            return;
+        };
+        if let Err(e) = write_store(&text) {
+            warn!("failed to save settings: {e}");
+        }
+    }
+}
+
+/// Plugin that restores settings before Startup and saves them when changed
+/// and on the way out
+pub struct PersistPlugin;
+
+impl Plugin for PersistPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PendingSave>()
+            .add_systems(PreStartup, load_settings)
+            .add_systems(
+                Last,
+                (
+                    // Building a snapshot clones a fair bit, only bother when
+                    // something that goes in it changed
+                    snapshot_settings.run_if(
+                        resource_changed::<FeatureToggles>
+                            .or(resource_changed::<InputActions>)
+                            .or(resource_changed::<ClearColor>)
+                            .or(resource_changed::<TvSettingsResource>)
+                            .or(resource_changed::<CrtPresets>)
+                            .or(resource_changed::<HelpHint>)
+                            .or(resource_changed::<PowerSettings>)
+                            .or(resource_changed::<Pacing>),
+                    ),
+                    flush_settings.run_if(
+                        bevy::time::common_conditions::on_real_timer(
+                            std::time::Duration::from_secs_f32(SAVE_INTERVAL_SECS),
+                        )
+                        .or(on_message::<AppExit>)
+                        .or(on_message::<WindowCloseRequested>),
+                    ),
+                )
+                    .chain(),
+            );
+
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Startup, flush_on_page_hide);
+    }
+}
+
+/// Xdg config file path, $XDG_CONFIG_HOME if set and absolute otherwise
+/// $HOME/.config
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_path(
+    xdg_config_home: Option<String>,
+    home: Option<String>,
+) -> Option<std::path::PathBuf> {
+    use std::path::PathBuf;
+
+    let base = xdg_config_home
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| {
+            home.filter(|h| !h.is_empty())
+                .map(|h| PathBuf::from(h).join(".config"))
+        })?;
+
+    Some(base.join(SETTINGS_KEY).join("settings.ron"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn settings_path() -> Option<std::path::PathBuf> {
+    config_path(
+        std::env::var("XDG_CONFIG_HOME").ok(),
+        std::env::var("HOME").ok(),
+    )
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_store() -> Option<String> {
+    std::fs::read_to_string(settings_path()?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_store(text: &str) -> Result<(), String> {
+    let path = settings_path().ok_or("no config directory found")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, text).map_err(|e| e.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_store() -> Option<String> {
+    local_storage()?.get_item(SETTINGS_KEY).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_store(text: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or("localStorage unavailable")?
+        .set_item(SETTINGS_KEY, text)
+        .map_err(|e| format!("{e:?}"))
+}
+
+/// Parse saved settings, anything unparseable just means defaults
+pub fn parse_settings(text: &str) -> SavedSettings {
+    ron::from_str(text).unwrap_or_else(|e| {
+        warn!("ignoring unreadable saved settings: {e}");
+        SavedSettings::default()
+    })
+}
+
+/// Restore saved settings into resources before anything in Startup runs
+pub fn load_settings(
+    mut commands: Commands,
+    mut presets: ResMut<CrtPresets>,
+    mut toggles: ResMut<FeatureToggles>,
+    mut actions: ResMut<InputActions>,
+    mut hint: ResMut<HelpHint>,
+    mut power: ResMut<PowerSettings>,
+    mut pacing: ResMut<Pacing>,
+) {
+    let saved = read_store()
+        .map(|text| parse_settings(&text))
+        .unwrap_or_default();
+
+    for (id, enabled) in &saved.toggles {
+        toggles.set(id, *enabled);
+    }
+    for (id, bindings) in &saved.bindings {
+        actions.set_bindings(id, bindings.clone());
+    }
+
+    hint.dismissed = saved.help_hint_dismissed;
+    *power = saved.power.clone();
+    *pacing = saved.pacing.clone();
+
+    commands.insert_resource(ClearColor(saved.clear_color()));
+    commands.insert_resource(TvSettingsResource {
+        settings: saved.crt.into(),
+    });
+
+    // Through save_user so anything saved before builtins were protected
+    // can't shadow one
+    presets.user.clear();
+    for preset in saved.crt_presets.iter().cloned() {
+        presets.save_user(preset);
+    }
+    presets.active = saved.crt_active.clone();
+    presets.blend_secs = saved.crt_blend_secs;
+
+    commands.insert_resource(saved);
+}
+
+/// Snapshot current state, queueing it to be written if anything differs from
+/// last time
+#[allow(clippy::too_many_arguments)]
+fn snapshot_settings(
+    mut saved: ResMut<SavedSettings>,
+    pending: Res<PendingSave>,
+    url: Option<Res<UrlOverrides>>,
+    clear_color: Res<ClearColor>,
+    tv_settings: Res<TvSettingsResource>,
+    presets: Res<CrtPresets>,
+    toggles: Res<FeatureToggles>,
+    actions: Res<InputActions>,
+    hint: Res<HelpHint>,
+    power: Res<PowerSettings>,
+    pacing: Res<Pacing>,
+) {
+    let mut current = SavedSettings {
+        toggles: toggles
+            .entries()
+            .iter()
+            .map(|e| (e.id.to_string(), e.enabled))
+            .collect(),
+        bindings: actions
+            .customized()
+            .map(|e| (e.id.to_string(), e.bindings.clone()))
+            .collect(),
+        clear_color: clear_color.0.to_srgba().to_f32_array(),
+        crt: tv_settings.settings.into(),
+        crt_active: presets.active.clone(),
+        crt_blend_secs: presets.blend_secs,
+        crt_presets: presets.user.clone(),
+        help_hint_dismissed: hint.dismissed,
+        power: power.clone(),
+        pacing: pacing.clone(),
+    };
+    if let Some(url) = url {
+        url.keep_saved(&mut current, &saved);
+    }
+
+    if current == *saved {
+        return;
+    }
+
+    match ron::ser::to_string_pretty(&current, ron::ser::PrettyConfig::default()) {
+        Ok(text) => pending.set(text),
+        Err(e) => warn!("failed to serialize settings: {e}"),
+    }
+
+    *saved = current;
+}
+
+fn flush_settings(pending: Res<PendingSave>) {
+    pending.flush();
+}
+
+/// Browsers don't say when a tab's closing, hiding the page is the last
+/// reliable chance to save and nothing runs after it
+#[cfg(target_arch = "wasm32")]
+fn flush_on_page_hide(pending: Res<PendingSave>) {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen::closure::Closure;
+
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let pending = pending.clone();
+    let flush = Closure::<dyn Fn()>::new(move || pending.flush());
+    let callback = flush.as_ref().unchecked_ref();
+
+    let listened = window
+        .add_event_listener_with_callback("pagehide", callback)
+        .and_then(|_| match window.document() {
+            Some(document) => {
+                document.add_event_listener_with_callback("visibilitychange", callback)
+            }
+            None => Ok(()),
+        });
+    if let Err(e) = listened {
+        warn!("can't save settings when the page closes: {e:?}");
+    }
+    // Listening for as long as the page is open
+    flush.forget();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Gesture;
+
+    #[test]
+    fn test_config_path_xdg() {
+        let result = config_path(Some("/xdg".to_string()), Some("/home/me".to_string()));
+        assert_eq!(
+            result,
+            Some(std::path::PathBuf::from("/xdg/mitchty/settings.ron"))
+        );
+    }
+
+    #[test]
+    fn test_config_path_home_fallback() {
+        // Relative XDG_CONFIG_HOME is invalid per the spec and gets ignored
+        let result = config_path(Some("relative".to_string()), Some("/home/me".to_string()));
+        assert_eq!(
+            result,
+            Some(std::path::PathBuf::from(
+                "/home/me/.config/mitchty/settings.ron"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_config_path_none() {
+        assert_eq!(config_path(None, None), None);
+    }
+
+    #[test]
+    fn test_settings_roundtrip() {
+        let settings = SavedSettings {
+            toggles: BTreeMap::from([("tv".to_string(), false)]),
+            bindings: BTreeMap::from([(
+                "tv".to_string(),
+                vec![Binding::Key(KeyCode::KeyY), Binding::Touch(Gesture::Tap)],
+            )]),
+            clear_color: [0.1, 0.2, 0.3, 1.0],
+            ..default()
+        };
+        let text =
+            ron::ser::to_string_pretty(&settings, ron::ser::PrettyConfig::default()).unwrap();
+        assert_eq!(parse_settings(&text), settings);
+    }
+
+    #[test]
+    fn test_partial_settings_use_defaults() {
+        let settings = parse_settings(r#"(toggles: {"fps": true})"#);
+        assert_eq!(settings.toggles.get("fps"), Some(&true));
+        assert_eq!(settings.toggles.get("tv"), None);
+        assert_eq!(settings.crt, CrtParams::default());
+    }
+
+    #[test]
+    fn test_garbage_settings_use_defaults() {
+        assert_eq!(parse_settings("not ron"), SavedSettings::default());
+    }
+}
@@ -0,0 +1,264 @@
+use std::time::Duration;
+
+use bevy::ecs::system::SystemParam;
+use bevy::input::gamepad::{GamepadAxisChangedEvent, GamepadButtonChangedEvent};
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::mouse::{MouseButtonInput, MouseWheel};
+use bevy::input::touch::TouchInput;
+use bevy::prelude::*;
+use bevy::window::{CursorMoved, PrimaryWindow, WindowOccluded};
+use bevy::winit::{UpdateMode, WinitSettings};
+use serde::{Deserialize, Serialize};
+
+use crate::pacing::{FrameLimit, Pacing};
+
+/// Update rate while the window is hidden or minimized, just enough to keep
+/// saving settings and notice it coming back
+const HIDDEN_WAIT: Duration = Duration::from_secs(1);
+
+/// How hard to save power, saved with the other settings
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerSettings {
+    /// Throttle at all, off always runs flat out
+    pub enabled: bool,
+    /// Seconds without any input before throttling to idle_fps
+    pub idle_after_secs: f32,
+    /// Frame rate while idle or unfocused
+    pub idle_fps: f32,
+}
+
+impl Default for PowerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            idle_after_secs: 60.0,
+            idle_fps: 15.0,
+        }
+    }
+}
+
+/// What the app is currently doing to save power
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PowerMode {
+    /// Running flat out
+    #[default]
+    Active,
+    /// Nobody's touched anything for a while
+    Idle,
+    /// Another window has focus
+    Unfocused,
+    /// Minimized, covered up or in a background tab
+    Hidden,
+}
+
+impl PowerMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            PowerMode::Active => "Active",
+            PowerMode::Idle => "Idle",
+            PowerMode::Unfocused => "Unfocused",
+            PowerMode::Hidden => "Hidden",
+        }
+    }
+
+    /// Whether frames are being held back on purpose, so slow frames right now
+    /// say nothing about how the machine is coping
+    pub fn is_throttled(self) -> bool {
+        self != PowerMode::Active
+    }
+
+    /// Mode for the window's state and how long since the last input
+    pub fn pick(settings: &PowerSettings, focused: bool, hidden: bool, idle_secs: f32) -> Self {
+        if !settings.enabled {
+            PowerMode::Active
+        } else if hidden {
+            PowerMode::Hidden
+        } else if !focused {
+            PowerMode::Unfocused
+        } else if idle_secs >= settings.idle_after_secs {
+            PowerMode::Idle
+        } else {
+            PowerMode::Active
+        }
+    }
+
+    /// Winit update mode for this power mode under a frame limit. The
+    /// throttled ones still wake straight away for input on the window, that's
+    /// what makes resuming instant.
+    pub fn update_mode(self, settings: &PowerSettings, limit: FrameLimit) -> UpdateMode {
+        match self {
+            PowerMode::Active => limit.update_mode().unwrap_or(UpdateMode::Continuous),
+            PowerMode::Idle | PowerMode::Unfocused => {
+                let fps = settings.idle_fps.min(limit.fps().unwrap_or(f32::MAX));
+                UpdateMode::reactive_low_power(Duration::from_secs_f32(1.0 / fps.max(1.0)))
+            }
+            PowerMode::Hidden => UpdateMode::reactive_low_power(HIDDEN_WAIT),
+        }
+    }
+}
+
+/// Anything someone does that should wake things back up
+#[derive(SystemParam)]
+struct UserInput<'w, 's> {
+    keys: MessageReader<'w, 's, KeyboardInput>,
+    buttons: MessageReader<'w, 's, MouseButtonInput>,
+    cursor: MessageReader<'w, 's, CursorMoved>,
+    wheel: MessageReader<'w, 's, MouseWheel>,
+    touches: MessageReader<'w, 's, TouchInput>,
+    gamepad_buttons: MessageReader<'w, 's, GamepadButtonChangedEvent>,
+    gamepad_axes: MessageReader<'w, 's, GamepadAxisChangedEvent>,
+}
+
+impl UserInput<'_, '_> {
+    /// Whether anything came in since last time
+    fn any(&mut self) -> bool {
+        // Read everything, stopping at the first would leave the rest for
+        // next frame
+        let inputs = [
+            self.keys.read().count(),
+            self.buttons.read().count(),
+            self.cursor.read().count(),
+            self.wheel.read().count(),
+            self.touches.read().count(),
+            self.gamepad_buttons.read().count(),
+            self.gamepad_axes.read().count(),
+        ];
+        inputs.iter().any(|n| *n > 0)
+    }
+}
+
+/// Plugin dropping to low power winit update modes when the window is
+/// unfocused, hidden or idle
+pub struct PowerPlugin;
+
+impl Plugin for PowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PowerSettings>()
+            .init_resource::<PowerMode>()
+            .add_systems(Update, update_power_mode);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_power_mode(
+    time: Res<Time<Real>>,
+    settings: Res<PowerSettings>,
+    pacing: Res<Pacing>,
+    windows: Query<(Entity, &Window), With<PrimaryWindow>>,
+    mut occlusion: MessageReader<WindowOccluded>,
+    mut input: UserInput,
+    mut hidden: Local<bool>,
+    mut last_input: Local<Option<Duration>>,
+    mut mode: ResMut<PowerMode>,
+    mut winit: ResMut<WinitSettings>,
+) {
+    let Ok((entity, window)) = windows.single() else {
+        return;
+    };
+    for event in occlusion.read() {
+        if event.window == entity {
+            *hidden = event.occluded;
+        }
+    }
+
+    let now = time.elapsed();
+    if input.any() || last_input.is_none() {
+        *last_input = Some(now);
+    }
+    let idle_secs = (now - last_input.unwrap_or(now)).as_secs_f32();
+
+    let next = PowerMode::pick(&settings, window.focused, *hidden, idle_secs);
+    if mode.set_if_neq(next) {
+        debug!("power mode {}", next.label());
+    }
+
+    // Same mode either way, which one winit picks by focus doesn't matter
+    let update_mode = next.update_mode(&settings, pacing.limit);
+    if winit.focused_mode != update_mode || winit.unfocused_mode != update_mode {
+        winit.focused_mode = update_mode;
+        winit.unfocused_mode = update_mode;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_prefers_hidden_then_unfocused_then_idle() {
+        let settings = PowerSettings::default();
+        let idle = settings.idle_after_secs;
+        assert_eq!(
+            PowerMode::pick(&settings, true, false, 0.0),
+            PowerMode::Active
+        );
+        assert_eq!(
+            PowerMode::pick(&settings, true, false, idle),
+            PowerMode::Idle
+        );
+        assert_eq!(
+            PowerMode::pick(&settings, false, false, 0.0),
+            PowerMode::Unfocused
+        );
+        assert_eq!(
+            PowerMode::pick(&settings, false, true, idle),
+            PowerMode::Hidden
+        );
+
+        let off = PowerSettings {
+            enabled: false,
+            ..default()
+        };
+        assert_eq!(PowerMode::pick(&off, false, true, idle), PowerMode::Active);
+    }
+
+    #[test]
+    fn test_idle_waits_one_idle_frame() {
+        let settings = PowerSettings {
+            idle_fps: 10.0,
+            ..default()
+        };
+        assert_eq!(
+            PowerMode::Active.update_mode(&settings, FrameLimit::Uncapped),
+            UpdateMode::Continuous
+        );
+        let UpdateMode::Reactive {
+            wait,
+            react_to_device_events,
+            ..
+        } = PowerMode::Idle.update_mode(&settings, FrameLimit::Uncapped)
+        else {
+            panic!("idle should be reactive");
+        };
+        assert!((wait.as_secs_f32() - 0.1).abs() < 1e-6);
+        // Mouse moving about elsewhere on the screen isn't using this
+        assert!(!react_to_device_events);
+    }
+
+    #[test]
+    fn test_frame_limit_caps_every_mode() {
+        let settings = PowerSettings {
+            idle_fps: 30.0,
+            ..default()
+        };
+        let limit = FrameLimit::Fps30;
+        assert_eq!(
+            PowerMode::Active.update_mode(&settings, limit),
+            limit.update_mode().unwrap()
+        );
+        // Idle never runs faster than the limit, nor the limit faster than idle
+        let idle = |idle_fps, limit| {
+            let settings = PowerSettings {
+                idle_fps,
+                ..default()
+            };
+            match PowerMode::Idle.update_mode(&settings, limit) {
+                UpdateMode::Reactive { wait, .. } => wait.as_secs_f32(),
+                UpdateMode::Continuous => 0.0,
+            }
+        };
+        assert!((idle(60.0, FrameLimit::Fps30) - 1.0 / 30.0).abs() < 1e-6);
+        assert!((idle(10.0, FrameLimit::Fps120) - 0.1).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,462 @@
+use bevy::anti_alias::smaa::Smaa;
+use bevy::camera::RenderTarget;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureFormat};
+use bevy::window::{PrimaryWindow, WindowRef};
+use bevy_egui::PrimaryEguiContext;
+
+use crate::MainCamera;
+use crate::formation::MAX_CUBES;
+use crate::pacing::Pacing;
+use crate::power::PowerMode;
+use crate::stats::{FrameStats, FrameTimes};
+use crate::tuning::SceneTuning;
+
+/// Frame time over budget by this much counts as missing the target
+const MISS_MARGIN: f32 = 1.15;
+/// Seconds of missed frames before dropping a level
+const DOWN_AFTER: f32 = 2.0;
+/// Seconds of frames on target before trying a level up
+const UP_AFTER: f32 = 5.0;
+/// Longest the governor backs off for after level ups that didn't hold
+const MAX_UP_WAIT: f32 = 60.0;
+
+/// One step of the quality ladder
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QualityLevel {
+    pub label: &'static str,
+    /// Fraction of the window resolution the scene renders at
+    pub render_scale: f32,
+    pub msaa: Msaa,
+    /// Cheaper antialiasing than msaa, only used with msaa off
+    pub smaa: bool,
+    /// Whether the tv effect is allowed at all
+    pub tv: bool,
+    /// Multiplier on the environment map intensity, 0 takes it off entirely
+    pub env_map: f32,
+    /// Most cubes the formation gets to show
+    pub max_cubes: usize,
+}
+
+/// Best to worst, the first one is the scene as it always looked
+pub const LEVELS: [QualityLevel; 5] = [
+    QualityLevel {
+        label: "Ultra",
+        render_scale: 1.0,
+        msaa: Msaa::Sample4,
+        smaa: false,
+        tv: true,
+        env_map: 1.0,
+        max_cubes: MAX_CUBES,
+    },
+    QualityLevel {
+        label: "High",
+        render_scale: 1.0,
+        msaa: Msaa::Off,
+        smaa: true,
+        tv: true,
+        env_map: 1.0,
+        max_cubes: MAX_CUBES,
+    },
+    QualityLevel {
+        label: "Medium",
+        render_scale: 0.75,
+        msaa: Msaa::Off,
+        smaa: true,
+        tv: true,
+        env_map: 0.5,
+        max_cubes: 250,
+    },
+    QualityLevel {
+        label: "Low",
+        render_scale: 0.5,
+        msaa: Msaa::Off,
+        smaa: false,
+        tv: false,
+        env_map: 0.25,
+        max_cubes: 100,
+    },
+    QualityLevel {
+        label: "Minimal",
+        render_scale: 0.5,
+        msaa: Msaa::Off,
+        smaa: false,
+        tv: false,
+        env_map: 0.0,
+        max_cubes: 27,
+    },
+];
+
+/// Quality the scene renders at, either picked by the governor or by hand
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Quality {
+    /// Index into LEVELS, 0 is the best
+    pub level: usize,
+    /// Let the governor move level around, off keeps whatever was picked
+    pub auto: bool,
+    /// Frame rate the governor tries to hold
+    pub target_fps: f32,
+}
+
+impl Default for Quality {
+    fn default() -> Self {
+        Self {
+            level: 0,
+            auto: true,
+            target_fps: 60.0,
+        }
+    }
+}
+
+impl Quality {
+    pub fn current(&self) -> &QualityLevel {
+        &LEVELS[self.level.min(LEVELS.len() - 1)]
+    }
+}
+
+/// Watches frame time and decides when to change level. Dropping is quick,
+/// going back up waits for a good while on target and backs off further every
+/// time a level up gets dropped again straight away, so a machine right on
+/// the edge doesn't flicker between two levels.
+#[derive(Resource, Debug)]
+struct Governor {
+    /// Seconds frames have been over budget
+    slow_for: f32,
+    /// Seconds frames have been on budget
+    fast_for: f32,
+    /// Seconds on target needed before the next level up
+    up_wait: f32,
+    /// Seconds since the last level up, None if the last change was down
+    since_up: Option<f32>,
+    /// Frames since the level last changed, older ones were drawn at another
+    /// level so they're left out of the average
+    frames: usize,
+}
+
+impl Default for Governor {
+    fn default() -> Self {
+        Self {
+            slow_for: 0.0,
+            fast_for: 0.0,
+            up_wait: UP_AFTER,
+            since_up: None,
+            frames: 0,
+        }
+    }
+}
+
+impl Governor {
+    /// Feed in the recent frame times oldest first, returns the level to switch
+    /// to if it's time to change. Goes by the average rather than the last
+    /// frame so frames alternating fast and slow still count as slow.
+    fn update(&mut self, level: usize, history: &[f64], dt: f32, target_fps: f32) -> Option<usize> {
+        self.frames += 1;
+        let recent = &history[history.len().saturating_sub(self.frames)..];
+        let frame_ms = FrameStats::from_frame_times(recent)?.frame_ms as f32;
+        let budget_ms = 1000.0 / target_fps.max(1.0);
+        if let Some(since) = &mut self.since_up {
+            *since += dt;
+        }
+
+        if frame_ms > budget_ms * MISS_MARGIN {
+            self.slow_for += dt;
+            self.fast_for = 0.0;
+        } else {
+            self.fast_for += dt;
+            self.slow_for = 0.0;
+        }
+
+        if self.slow_for >= DOWN_AFTER && level + 1 < LEVELS.len() {
+            // Couldn't hold the last level up, wait longer before the next
+            if self.since_up.is_some_and(|since| since < self.up_wait) {
+                self.up_wait = (self.up_wait * 2.0).min(MAX_UP_WAIT);
+            }
+            self.since_up = None;
+            self.slow_for = 0.0;
+            self.frames = 0;
+            return Some(level + 1);
+        }
+        if self.fast_for >= self.up_wait && level > 0 {
+            self.since_up = Some(0.0);
+            self.fast_for = 0.0;
+            self.frames = 0;
+            return Some(level - 1);
+        }
+        None
+    }
+}
+
+/// What the scene camera renders into when it's scaled below the window size
+#[derive(Resource)]
+struct SceneImage(Handle<Image>);
+
+/// Sprite showing the scaled scene stretched over the window
+#[derive(Component)]
+struct SceneSprite;
+
+/// Plugin for the quality levels, the governor picking between them and
+/// pushing the current one onto the scene
+pub struct QualityPlugin;
+
+impl Plugin for QualityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Quality>()
+            .init_resource::<Governor>()
+            .add_systems(Startup, setup_display_camera)
+            .add_systems(
+                Update,
+                (
+                    govern_quality,
+                    apply_render_scale,
+                    (apply_antialiasing, apply_environment_light)
+                        .run_if(resource_changed::<Quality>.or(resource_changed::<SceneTuning>)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// The scene camera can't show a lower resolution render by itself, so this
+/// camera sits on top at full resolution with the ui and egui, showing the
+/// scene image when there is one
+fn setup_display_camera(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let image = images.add(Image::new_target_texture(
+        1,
+        1,
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: 1,
+            // See through to the scene camera when it draws to the window
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+            ..default()
+        },
+        Msaa::Off,
+        IsDefaultUiCamera,
+        PrimaryEguiContext,
+    ));
+    commands.spawn((
+        Sprite::from_image(image.clone()),
+        Visibility::Hidden,
+        SceneSprite,
+    ));
+    commands.insert_resource(SceneImage(image));
+}
+
+fn govern_quality(
+    time: Res<Time<Real>>,
+    frame_times: Res<FrameTimes>,
+    power: Res<PowerMode>,
+    pacing: Res<Pacing>,
+    mut governor: ResMut<Governor>,
+    mut quality: ResMut<Quality>,
+) {
+    if !quality.auto {
+        *governor = Governor::default();
+        return;
+    }
+    // Frames held back to save power aren't the machine struggling, start
+    // counting again once it's back to full speed
+    if power.is_throttled() {
+        governor.slow_for = 0.0;
+        governor.fast_for = 0.0;
+        governor.frames = 0;
+        return;
+    }
+
+    // No point chasing a frame rate the limiter won't allow
+    let target_fps = pacing
+        .limit
+        .fps()
+        .map_or(quality.target_fps, |fps| fps.min(quality.target_fps));
+    if let Some(level) = governor.update(
+        quality.level,
+        &frame_times.history,
+        time.delta_secs(),
+        target_fps,
+    ) {
+        info!(
+            "quality {} -> {}",
+            quality.current().label,
+            LEVELS[level].label
+        );
+        quality.level = level;
+    }
+}
+
+/// Point the scene camera at the window or at a scaled down image, keeping
+/// the image and sprite sized to the window as it changes
+fn apply_render_scale(
+    quality: Res<Quality>,
+    scene_image: Res<SceneImage>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut images: ResMut<Assets<Image>>,
+    mut cameras: Query<&mut Camera, With<MainCamera>>,
+    mut sprites: Query<(&mut Sprite, &mut Visibility), With<SceneSprite>>,
+) {
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let scale = quality.current().render_scale;
+    let scaled = scale < 1.0;
+
+    let target = if scaled {
+        let size = (window.physical_size().as_vec2() * scale)
+            .round()
+            .as_uvec2()
+            .max(UVec2::ONE);
+        if let Some(image) = images.get(&scene_image.0)
+            && image.size() != size
+            && let Some(image) = images.get_mut(&scene_image.0)
+        {
+            image.resize(Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            });
+        }
+        RenderTarget::from(scene_image.0.clone())
+    } else {
+        RenderTarget::Window(WindowRef::Primary)
+    };
+
+    for mut camera in cameras.iter_mut() {
+        // The image handle never changes so which kind of target is enough
+        if matches!(camera.target, RenderTarget::Image(_)) != scaled {
+            camera.target = target.clone();
+        }
+    }
+    for (mut sprite, mut visibility) in sprites.iter_mut() {
+        if sprite.custom_size != Some(window.size()) {
+            sprite.custom_size = Some(window.size());
+        }
+        visibility.set_if_neq(if scaled {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
+
+/// Smaa doesn't mix with msaa so it only goes on with msaa off
+fn apply_antialiasing(
+    quality: Res<Quality>,
+    mut cameras: Query<(Entity, &mut Msaa), With<MainCamera>>,
+    mut commands: Commands,
+) {
+    let level = quality.current();
+    for (entity, mut msaa) in cameras.iter_mut() {
+        msaa.set_if_neq(level.msaa);
+        if level.smaa && level.msaa == Msaa::Off {
+            commands.entity(entity).insert(Smaa::default());
+        } else {
+            commands.entity(entity).remove::<Smaa>();
+        }
+    }
+}
+
+/// Environment light at the tuned intensity scaled by the quality level, at 0
+/// it comes off the camera and is kept here until it's wanted again
+fn apply_environment_light(
+    quality: Res<Quality>,
+    tuning: Res<SceneTuning>,
+    mut stashed: Local<Option<EnvironmentMapLight>>,
+    mut cameras: Query<(Entity, Option<&mut EnvironmentMapLight>), With<MainCamera>>,
+    mut commands: Commands,
+) {
+    let intensity = tuning.lighting.env_map_intensity * quality.current().env_map;
+
+    for (entity, light) in cameras.iter_mut() {
+        match light {
+            Some(light) if intensity <= 0.0 => {
+                *stashed = Some(light.clone());
+                commands.entity(entity).remove::<EnvironmentMapLight>();
+            }
+            Some(mut light) => light.intensity = intensity,
+            None if intensity > 0.0 => {
+                if let Some(mut light) = stashed.take() {
+                    light.intensity = intensity;
+                    commands.entity(entity).insert(light);
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run the governor at a steady frame time for secs, returning the level
+    fn run(governor: &mut Governor, mut level: usize, frame_ms: f64, secs: f32) -> usize {
+        let dt = 0.125;
+        for _ in 0..(secs / dt).round() as usize {
+            if let Some(next) = governor.update(level, &[frame_ms], dt, 60.0) {
+                level = next;
+            }
+        }
+        level
+    }
+
+    #[test]
+    fn test_first_level_is_the_original_look() {
+        let ultra = LEVELS[0];
+        assert_eq!(ultra.render_scale, 1.0);
+        assert_eq!(ultra.msaa, Msaa::default());
+        assert!(ultra.tv && !ultra.smaa);
+        assert_eq!(ultra.env_map, 1.0);
+        assert_eq!(ultra.max_cubes, MAX_CUBES);
+    }
+
+    #[test]
+    fn test_drops_when_slow_and_recovers() {
+        let mut governor = Governor::default();
+        // A hitch shorter than DOWN_AFTER changes nothing
+        assert_eq!(run(&mut governor, 0, 40.0, 1.0), 0);
+        assert_eq!(run(&mut governor, 0, 40.0, DOWN_AFTER * 2.0 + 0.5), 2);
+        // Stops at the bottom
+        assert_eq!(run(&mut governor, 2, 100.0, 60.0), LEVELS.len() - 1);
+        // Comes back up one level per UP_AFTER
+        assert_eq!(run(&mut governor, 4, 10.0, UP_AFTER + 0.5), 3);
+    }
+
+    #[test]
+    fn test_backs_off_after_a_failed_level_up() {
+        let mut governor = Governor::default();
+        let level = run(&mut governor, 0, 40.0, DOWN_AFTER + 0.5);
+        assert_eq!(level, 1);
+        let level = run(&mut governor, level, 10.0, UP_AFTER + 0.5);
+        assert_eq!(level, 0);
+        // Too slow at the better level, straight back down
+        let level = run(&mut governor, level, 40.0, DOWN_AFTER + 0.5);
+        assert_eq!(level, 1);
+        // Now it takes twice as long to try again
+        assert_eq!(run(&mut governor, level, 10.0, UP_AFTER + 0.5), 1);
+        assert_eq!(run(&mut governor, level, 10.0, UP_AFTER), 0);
+    }
+
+    #[test]
+    fn test_alternating_frames_go_by_the_average() {
+        // Every other frame over budget, run at about 60fps
+        let run = |fast: f64, slow: f64| {
+            let mut governor = Governor::default();
+            let mut history = Vec::new();
+            let mut level = 0;
+            for i in 0..(DOWN_AFTER * 60.0) as usize + 60 {
+                history.push(if i % 2 == 0 { fast } else { slow });
+                if let Some(next) = governor.update(level, &history, 1.0 / 60.0, 60.0) {
+                    level = next;
+                }
+            }
+            level
+        };
+        // Averages 21ms, too slow even though half the frames are fine
+        assert_eq!(run(10.0, 32.0), 1);
+        // Averages 16ms, fine even though half the frames are over budget
+        assert_eq!(run(10.0, 22.0), 0);
+    }
+}
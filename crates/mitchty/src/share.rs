@@ -0,0 +1,442 @@
+use bevy::prelude::*;
+
+use crate::crt::{CrtParams, CrtPresets};
+use crate::persist::SavedSettings;
+use crate::toggle::FeatureToggles;
+use crate::{SceneSeed, TvSettingsResource};
+
+/// Where share links point at when there's no browser location to use
+#[cfg(not(target_arch = "wasm32"))]
+const SITE_URL: &str = "https://mitchty.github.io/";
+
+/// Scene overrides from url parameters, anything None leaves the restored
+/// settings alone. Kept around after startup so they only last the visit and
+/// don't get saved over the visitor's own settings.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct UrlOverrides {
+    /// Feature toggle id and state, e.g. tv=0
+    pub toggles: Vec<(String, bool)>,
+    pub seed: Option<u64>,
+    pub clear: Option<Color>,
+    /// CRT preset name
+    pub crt: Option<String>,
+    /// CRT settings that aren't a preset, only used without a preset name
+    pub crt_params: Option<CrtParams>,
+}
+
+impl UrlOverrides {
+    /// Put the saved values back for anything still the way the url set it,
+    /// anything changed since is the visitor's and gets saved as usual
+    pub fn keep_saved(&self, current: &mut SavedSettings, saved: &SavedSettings) {
+        for (id, enabled) in &self.toggles {
+            if current.toggles.get(id) != Some(enabled) {
+                continue;
+            }
+            match saved.toggles.get(id) {
+                Some(before) => current.toggles.insert(id.clone(), *before),
+                None => current.toggles.remove(id),
+            };
+        }
+        if self
+            .clear
+            .is_some_and(|clear| clear.to_srgba().to_f32_array() == current.clear_color)
+        {
+            current.clear_color = saved.clear_color;
+        }
+
+        let crt_from_url = match (&self.crt, self.crt_params) {
+            (Some(name), _) => current.crt_active.as_ref() == Some(name),
+            (None, Some(params)) => current.crt_active.is_none() && current.crt == params,
+            (None, None) => false,
+        };
+        if crt_from_url {
+            current.crt = saved.crt;
+            current.crt_active = saved.crt_active.clone();
+        }
+    }
+}
+
+/// Plugin to apply url parameters on startup
+pub struct ShareLinkPlugin;
+
+impl Plugin for ShareLinkPlugin {
+    fn build(&self, _app: &mut App) {
+        // Only the browser build has a url to read
+        #[cfg(target_arch = "wasm32")]
+        _app.add_systems(
+            PreStartup,
+            apply_url_overrides.after(crate::persist::load_settings),
+        );
+    }
+}
+
+/// Parse on/off style values
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "on" | "true" | "yes" => Some(true),
+        "0" | "off" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Decode %xx escapes and + as space, bad escapes are left as is
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let decoded = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                if let Some(b) = decoded {
+                    out.push(b);
+                    i += 3;
+                    continue;
+                }
+                out.push(b'%');
+            }
+            b'+' => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// CRT settings as comma separated numbers in CrtParams field order
+fn encode_crt_params(params: &CrtParams) -> String {
+    [
+        params.screen_shape_factor,
+        params.rows,
+        params.brightness,
+        params.edges_transition_size,
+        params.channels_mask_min,
+    ]
+    .map(|v| v.to_string())
+    .join(",")
+}
+
+fn parse_crt_params(value: &str) -> Option<CrtParams> {
+    let values: Vec<f32> = value
+        .split(',')
+        .map(|v| v.parse().ok().filter(|v: &f32| v.is_finite()))
+        .collect::<Option<_>>()?;
+    let [
+        screen_shape_factor,
+        rows,
+        brightness,
+        edges_transition_size,
+        channels_mask_min,
+    ] = values[..]
+    else {
+        return None;
+    };
+    Some(CrtParams {
+        screen_shape_factor,
+        rows,
+        brightness,
+        edges_transition_size,
+        channels_mask_min,
+    })
+}
+
+/// Split key=value pairs out of everything after the ? or # in a url.
+///
+/// A # in a value is kept so clear=#202020 works unescaped, but a # followed
+/// by another key=value starts a new pair so ?tv=0#fps=1 works too.
+fn split_pairs(params: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+
+    for chunk in params.split('&') {
+        let mut parts = vec![chunk];
+        if let Some((head, tail)) = chunk.split_once('#')
+            && tail.contains('=')
+        {
+            parts = vec![head, tail];
+        }
+
+        for part in parts {
+            if part.is_empty() {
+                continue;
+            }
+            let (key, value) = part.split_once('=').unwrap_or((part, ""));
+            pairs.push((percent_decode(key), percent_decode(value)));
+        }
+    }
+
+    pairs
+}
+
+// Dead code allowed as these are unit tested natively but only called from the
+// wasm build.
+
+/// Parse the overrides out of a full url/href
+#[allow(dead_code)]
+pub fn parse_url(href: &str) -> UrlOverrides {
+    let params = match href.split_once('?') {
+        Some((_, rest)) => rest,
+        None => href.split_once('#').map(|(_, rest)| rest).unwrap_or(""),
+    };
+
+    let mut overrides = UrlOverrides::default();
+
+    for (key, value) in split_pairs(params) {
+        match key.as_str() {
+            "seed" => overrides.seed = value.parse().ok(),
+            "clear" => overrides.clear = Srgba::hex(&value).ok().map(Color::from),
+            "crt" => overrides.crt = Some(value),
+            "crt_params" => overrides.crt_params = parse_crt_params(&value),
+            // Anything else on/off is assumed to be a feature toggle id
+            _ => {
+                if let Some(enabled) = parse_bool(&value) {
+                    overrides.toggles.push((key, enabled));
+                }
+            }
+        }
+    }
+
+    overrides
+}
+
+/// Encode the state we care about as url parameters, the crt goes by preset
+/// name if there is one otherwise by its settings
+pub fn encode_params(
+    toggles: &FeatureToggles,
+    seed: u64,
+    clear: Color,
+    crt: Option<&str>,
+    crt_params: &CrtParams,
+) -> String {
+    let mut params = String::new();
+
+    for entry in toggles.entries() {
+        let flag = if entry.enabled { "1" } else { "0" };
+        params.push_str(&format!("{}={}&", entry.id, flag));
+    }
+
+    let clear = clear.to_srgba().to_hex();
+    params.push_str(&format!(
+        "seed={}&clear={}",
+        seed,
+        clear.trim_start_matches('#')
+    ));
+
+    if let Some(crt) = crt {
+        params.push_str("&crt=");
+        for b in crt.bytes() {
+            if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+                params.push(b as char);
+            } else {
+                params.push_str(&format!("%{b:02X}"));
+            }
+        }
+    } else {
+        params.push_str(&format!("&crt_params={}", encode_crt_params(crt_params)));
+    }
+
+    params
+}
+
+/// Base url share links get built from, minus any existing parameters
+pub fn base_url() -> String {
+    #[cfg(target_arch = "wasm32")]
+    {
+        web_sys::window()
+            .and_then(|w| {
+                let location = w.location();
+                Some(format!(
+                    "{}{}",
+                    location.origin().ok()?,
+                    location.pathname().ok()?
+                ))
+            })
+            .unwrap_or_default()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        SITE_URL.to_string()
+    }
+}
+
+/// Put anything passed in the url over the restored settings
+#[cfg(target_arch = "wasm32")]
+fn apply_url_overrides(
+    mut commands: Commands,
+    mut toggles: ResMut<FeatureToggles>,
+    mut seed: ResMut<SceneSeed>,
+    mut presets: ResMut<CrtPresets>,
+    mut clear_color: ResMut<ClearColor>,
+    mut tv_settings: ResMut<TvSettingsResource>,
+) {
+    let Some(href) = web_sys::window().and_then(|w| w.location().href().ok()) else {
+        return;
+    };
+
+    let overrides = parse_url(&href);
+    apply_overrides(
+        &overrides,
+        &mut toggles,
+        &mut seed,
+        &mut presets,
+        &mut clear_color,
+        &mut tv_settings,
+    );
+    commands.insert_resource(overrides);
+}
+
+/// Fold overrides into the live startup state, the saved settings are left
+/// alone
+#[allow(dead_code)]
+pub fn apply_overrides(
+    overrides: &UrlOverrides,
+    toggles: &mut FeatureToggles,
+    seed: &mut SceneSeed,
+    presets: &mut CrtPresets,
+    clear_color: &mut ClearColor,
+    tv_settings: &mut TvSettingsResource,
+) {
+    for (id, enabled) in &overrides.toggles {
+        toggles.set(id, *enabled);
+    }
+    if let Some(value) = overrides.seed {
+        seed.0 = value;
+    }
+    if let Some(clear) = overrides.clear {
+        clear_color.0 = clear;
+    }
+    if let Some(preset) = overrides
+        .crt
+        .as_ref()
+        .and_then(|name| presets.iter().find(|p| &p.name == name).cloned())
+    {
+        tv_settings.settings = preset.params.into();
+        presets.active = Some(preset.name);
+    } else if let Some(params) = overrides.crt_params {
+        tv_settings.settings = params.into();
+        presets.active = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::toggle::FeatureToggle;
+
+    #[test]
+    fn test_parse_url_example() {
+        let overrides =
+            parse_url("https://mitchty.github.io/?tv=0&fps=1&seed=42&clear=#202020&camera=off");
+        assert_eq!(
+            overrides.toggles,
+            vec![
+                ("tv".to_string(), false),
+                ("fps".to_string(), true),
+                ("camera".to_string(), false),
+            ]
+        );
+        assert_eq!(overrides.seed, Some(42));
+        assert_eq!(
+            overrides.clear,
+            Some(Color::from(Srgba::hex("202020").unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_parse_url_hash_only() {
+        let overrides = parse_url("https://mitchty.github.io/#hue=off&crt=broken%20tv");
+        assert_eq!(overrides.toggles, vec![("hue".to_string(), false)]);
+        assert_eq!(overrides.crt.as_deref(), Some("broken tv"));
+    }
+
+    #[test]
+    fn test_parse_url_query_and_hash() {
+        let overrides = parse_url("https://mitchty.github.io/?tv=1#fps=yes");
+        assert_eq!(
+            overrides.toggles,
+            vec![("tv".to_string(), true), ("fps".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn test_parse_url_garbage() {
+        let overrides = parse_url("https://mitchty.github.io/?tv=maybe&seed=x&clear=nope&%zz");
+        assert_eq!(overrides, UrlOverrides::default());
+        assert_eq!(
+            parse_url("https://mitchty.github.io/"),
+            UrlOverrides::default()
+        );
+    }
+
+    #[test]
+    fn test_encode_roundtrip() {
+        struct Tv;
+        struct Fps;
+
+        let mut app = App::new();
+        app.add_plugins((
+            FeatureToggle::<Tv>::new("tv", "TV", KeyCode::KeyT),
+            FeatureToggle::<Fps>::new("fps", "FPS", KeyCode::KeyF).off_by_default(),
+        ));
+        let toggles = app.world().resource::<FeatureToggles>();
+
+        let clear = Color::from(Srgba::hex("202020").unwrap());
+        let crt = CrtParams::default();
+        let params = encode_params(toggles, 42, clear, Some("broken tv"), &crt);
+        let overrides = parse_url(&format!("{}?{}", base_url(), params));
+
+        assert_eq!(
+            overrides,
+            UrlOverrides {
+                toggles: vec![("tv".to_string(), true), ("fps".to_string(), false)],
+                seed: Some(42),
+                clear: Some(clear),
+                crt: Some("broken tv".to_string()),
+                crt_params: None,
+            }
+        );
+
+        // Hand tuned crt settings aren't a preset, so they go by value
+        let crt = CrtParams {
+            rows: 97.5,
+            brightness: 1.25,
+            ..crt
+        };
+        let params = encode_params(toggles, 42, clear, None, &crt);
+        let overrides = parse_url(&format!("{}?{}", base_url(), params));
+        assert_eq!(overrides.crt, None);
+        assert_eq!(overrides.crt_params, Some(crt));
+    }
+
+    #[test]
+    fn test_overrides_are_not_saved() {
+        let saved = SavedSettings {
+            toggles: BTreeMap::from([("tv".to_string(), true)]),
+            ..default()
+        };
+        let overrides = parse_url("https://mitchty.github.io/?tv=0&fps=1&clear=202020&crt=arcade");
+
+        let mut current = SavedSettings {
+            toggles: BTreeMap::from([("tv".to_string(), false), ("fps".to_string(), true)]),
+            clear_color: overrides.clear.unwrap().to_srgba().to_f32_array(),
+            crt_active: Some("arcade".to_string()),
+            ..default()
+        };
+        overrides.keep_saved(&mut current, &saved);
+        assert_eq!(current, saved);
+
+        // Changed after the link set it, that's worth keeping
+        let mut current = SavedSettings {
+            toggles: BTreeMap::from([("tv".to_string(), true), ("fps".to_string(), false)]),
+            ..default()
+        };
+        overrides.keep_saved(&mut current, &saved);
+        assert_eq!(current.toggles.get("fps"), Some(&false));
+    }
+}
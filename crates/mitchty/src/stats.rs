@@ -0,0 +1,141 @@
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy_egui::egui;
+
+/// Frames the stats are worked out over, about 5 seconds at 60fps
+const HISTORY_FRAMES: usize = 300;
+/// Frame times the graph draws guide lines at, 60 and 30fps
+const GRAPH_GUIDES_MS: [f32; 2] = [1000.0 / 60.0, 1000.0 / 30.0];
+
+/// Frame timing over the last HISTORY_FRAMES frames
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    /// Frames per second going by the average frame time, not the average of
+    /// each frame's fps which overweights the fast ones
+    pub fps: f64,
+    pub frame_ms: f64,
+    /// Fps of the slowest 1% of frames on average
+    pub low_fps: f64,
+    pub max_ms: f64,
+}
+
+impl FrameStats {
+    /// Stats for some frame times in ms, None without any
+    pub fn from_frame_times(frame_times: &[f64]) -> Option<Self> {
+        if frame_times.is_empty() {
+            return None;
+        }
+
+        let mut sorted = frame_times.to_vec();
+        sorted.sort_by(|a, b| b.total_cmp(a));
+
+        let frame_ms = sorted.iter().sum::<f64>() / sorted.len() as f64;
+        let slowest = sorted.len().div_ceil(100);
+        let low_ms = sorted[..slowest].iter().sum::<f64>() / slowest as f64;
+
+        Some(Self {
+            fps: 1000.0 / frame_ms,
+            frame_ms,
+            low_fps: 1000.0 / low_ms,
+            max_ms: sorted[0],
+        })
+    }
+}
+
+/// Latest stats and the frame times they came from, oldest first
+#[derive(Resource, Default)]
+pub struct FrameTimes {
+    pub stats: Option<FrameStats>,
+    pub history: Vec<f64>,
+}
+
+/// Plugin collecting frame time stats from bevy's diagnostics
+pub struct FrameStatsPlugin;
+
+impl Plugin for FrameStatsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin::new(HISTORY_FRAMES));
+        }
+        app.init_resource::<FrameTimes>()
+            .add_systems(PostUpdate, collect_frame_times);
+    }
+}
+
+fn collect_frame_times(diagnostics: Res<DiagnosticsStore>, mut times: ResMut<FrameTimes>) {
+    let Some(frame_time) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FRAME_TIME) else {
+        return;
+    };
+
+    times.history.clear();
+    times.history.extend(frame_time.values());
+    times.stats = FrameStats::from_frame_times(&times.history);
+}
+
+/// Rolling frame time graph, taller is slower
+pub fn frame_time_graph(ui: &mut egui::Ui, history: &[f64]) {
+    let size = egui::vec2(ui.available_width().max(200.0), 80.0);
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    // Room for the 30fps line at least, more if something spiked past it
+    let top_ms = history
+        .iter()
+        .copied()
+        .fold(GRAPH_GUIDES_MS[1] as f64 * 1.25, f64::max) as f32;
+    let y = |ms: f32| rect.bottom() - (ms / top_ms) * rect.height();
+
+    for guide in GRAPH_GUIDES_MS {
+        let stroke = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+        painter.hline(rect.x_range(), y(guide), stroke);
+        painter.text(
+            egui::pos2(rect.left() + 2.0, y(guide)),
+            egui::Align2::LEFT_BOTTOM,
+            format!("{guide:.1} ms"),
+            egui::FontId::monospace(9.0),
+            ui.visuals().weak_text_color(),
+        );
+    }
+
+    if history.len() < 2 {
+        return;
+    }
+    let step = rect.width() / (HISTORY_FRAMES - 1) as f32;
+    // Newest frame on the right edge
+    let start = rect.right() - step * (history.len() - 1) as f32;
+    let points: Vec<egui::Pos2> = history
+        .iter()
+        .enumerate()
+        .map(|(i, ms)| egui::pos2(start + step * i as f32, y(*ms as f32)))
+        .collect();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1.5, egui::Color32::LIGHT_GREEN),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_frames_no_stats() {
+        assert_eq!(FrameStats::from_frame_times(&[]), None);
+    }
+
+    #[test]
+    fn test_hitches_show_up_in_low_and_max() {
+        // 198 smooth frames and two 50ms hitches
+        let mut times = vec![10.0; 198];
+        times.insert(50, 50.0);
+        times.push(50.0);
+
+        let stats = FrameStats::from_frame_times(&times).unwrap();
+        assert!((stats.frame_ms - 10.4).abs() < 1e-9);
+        assert!((stats.fps - 1000.0 / 10.4).abs() < 1e-9);
+        // 1% of 200 is the two hitches
+        assert!((stats.low_fps - 20.0).abs() < 1e-9);
+        assert_eq!(stats.max_ms, 50.0);
+    }
+}
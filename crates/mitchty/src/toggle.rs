@@ -0,0 +1,454 @@
+use std::any::TypeId;
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::input::{Binding, InputActions, register_action};
+
+/// Component put on target entities while feature T is on, e.g.
+/// Enabled<CubeRotation> on every cube.
+#[derive(Component)]
+pub struct Enabled<T: Send + Sync + 'static>(PhantomData<T>);
+
+impl<T: Send + Sync + 'static> Default for Enabled<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+/// One registered feature and its current state
+#[derive(Clone, Debug)]
+pub struct ToggleEntry {
+    /// Stable short name used for persistence and url parameters
+    pub id: &'static str,
+    /// Human readable name for ui
+    pub label: &'static str,
+    /// State on a fresh visit
+    pub default: bool,
+    pub enabled: bool,
+    /// Eased 0..1 of how far faded in the feature is, follows enabled over
+    /// ToggleRamp::secs
+    pub intensity: f32,
+    /// Linear 0..1 progress of the fade, intensity is this eased
+    progress: f32,
+    type_id: TypeId,
+}
+
+/// How long features take to fade in/out when toggled, and the curve
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct ToggleRamp {
+    pub secs: f32,
+    pub easing: EaseFunction,
+}
+
+impl Default for ToggleRamp {
+    fn default() -> Self {
+        Self {
+            secs: 0.6,
+            easing: EaseFunction::SineInOut,
+        }
+    }
+}
+
+/// Every registered feature toggle, this is the source of truth for whether a
+/// feature is on or not.
+#[derive(Resource, Default)]
+pub struct FeatureToggles {
+    entries: Vec<ToggleEntry>,
+}
+
+impl FeatureToggles {
+    pub fn entries(&self) -> &[ToggleEntry] {
+        &self.entries
+    }
+
+    /// Is feature T on, unregistered features are always off
+    pub fn is_enabled<T: 'static>(&self) -> bool {
+        let type_id = TypeId::of::<T>();
+        self.entries
+            .iter()
+            .any(|e| e.type_id == type_id && e.enabled)
+    }
+
+    /// How far faded in feature T is, 0 off to 1 fully on
+    pub fn intensity<T: 'static>(&self) -> f32 {
+        let type_id = TypeId::of::<T>();
+        self.entries
+            .iter()
+            .find(|e| e.type_id == type_id)
+            .map_or(0.0, |e| e.intensity)
+    }
+
+    /// Is feature T on or still fading out
+    pub fn is_active<T: 'static>(&self) -> bool {
+        let type_id = TypeId::of::<T>();
+        self.entries
+            .iter()
+            .any(|e| e.type_id == type_id && (e.enabled || e.intensity > 0.0))
+    }
+
+    /// Anything part way through a fade
+    fn is_ramping(&self) -> bool {
+        self.entries.iter().any(|e| e.progress != target(e.enabled))
+    }
+
+    /// Move every fade dt seconds along
+    fn ramp(&mut self, dt: f32, ramp: &ToggleRamp) {
+        let step = if ramp.secs <= 0.0 {
+            1.0
+        } else {
+            dt / ramp.secs
+        };
+
+        for entry in &mut self.entries {
+            let target = target(entry.enabled);
+            entry.progress = if entry.progress < target {
+                (entry.progress + step).min(target)
+            } else {
+                (entry.progress - step).max(target)
+            };
+            entry.intensity = if entry.progress == target {
+                target
+            } else {
+                ramp.easing.sample_clamped(entry.progress)
+            };
+        }
+    }
+
+    /// Jump every feature straight to its state, no fading
+    fn settle(&mut self) {
+        for entry in &mut self.entries {
+            entry.progress = target(entry.enabled);
+            entry.intensity = entry.progress;
+        }
+    }
+
+    /// Set a feature by id, returns false if there is no such feature
+    pub fn set(&mut self, id: &str, enabled: bool) -> bool {
+        match self.entries.iter_mut().find(|e| e.id == id) {
+            Some(entry) => {
+                entry.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Flip a feature by id
+    pub fn toggle(&mut self, id: &str) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+            entry.enabled = !entry.enabled;
+        }
+    }
+
+    /// Put every feature back to its default state
+    pub fn reset(&mut self) {
+        for entry in &mut self.entries {
+            entry.enabled = entry.default;
+        }
+    }
+
+    fn register(&mut self, entry: ToggleEntry) {
+        assert!(
+            self.entries.iter().all(|e| e.id != entry.id),
+            "feature toggle {} registered twice",
+            entry.id
+        );
+        self.entries.push(entry);
+    }
+}
+
+fn target(enabled: bool) -> f32 {
+    if enabled { 1.0 } else { 0.0 }
+}
+
+/// Sent whenever a feature is switched on or off
+#[derive(Message, Clone, Debug)]
+pub struct FeatureToggled {
+    pub id: &'static str,
+    pub enabled: bool,
+}
+
+/// Shared systems for all feature toggles, added automatically by the first
+/// FeatureToggle.
+pub struct FeatureTogglePlugin;
+
+impl Plugin for FeatureTogglePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FeatureToggles>()
+            .init_resource::<ToggleRamp>()
+            .add_message::<FeatureToggled>()
+            .add_systems(
+                Update,
+                (
+                    toggle_from_actions,
+                    ramp_toggles,
+                    send_toggled.run_if(resource_changed::<FeatureToggles>),
+                    log_toggled,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Register a feature with a default key binding and ui label, the feature id
+/// doubles as the input action id so it can be rebound, e.g.
+///
+/// ```ignore
+/// app.add_plugins(
+///     FeatureToggle::<CubeRotation>::new("cube", "Cube Rotation", KeyCode::KeyC)
+///         .applies_to::<Rotator>(),
+/// );
+/// ```
+pub struct FeatureToggle<T> {
+    entry: ToggleEntry,
+    bindings: Vec<Binding>,
+    apply: Option<fn(&mut App)>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Send + Sync + 'static> FeatureToggle<T> {
+    /// New feature that starts enabled
+    pub fn new(id: &'static str, label: &'static str, key: KeyCode) -> Self {
+        Self {
+            entry: ToggleEntry {
+                id,
+                label,
+                default: true,
+                enabled: true,
+                intensity: 1.0,
+                progress: 1.0,
+                type_id: TypeId::of::<T>(),
+            },
+            bindings: vec![Binding::Key(key)],
+            apply: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Another default binding on top of the key, e.g. a gamepad button
+    pub fn with_binding(mut self, binding: Binding) -> Self {
+        self.bindings.push(binding);
+        self
+    }
+
+    /// Start with the feature turned off
+    pub fn off_by_default(mut self) -> Self {
+        self.entry.default = false;
+        self.entry.enabled = false;
+        self.entry.intensity = 0.0;
+        self.entry.progress = 0.0;
+        self
+    }
+
+    /// Keep Enabled<T> on every entity with Target while the feature is on or
+    /// fading out
+    pub fn applies_to<Target: Component>(mut self) -> Self {
+        self.apply = Some(|app| {
+            app.add_systems(Update, apply_feature::<T, Target>.after(send_toggled));
+        });
+        self
+    }
+}
+
+impl<T: Send + Sync + 'static> Plugin for FeatureToggle<T> {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FeatureTogglePlugin>() {
+            app.add_plugins(FeatureTogglePlugin);
+        }
+
+        app.world_mut()
+            .resource_mut::<FeatureToggles>()
+            .register(self.entry.clone());
+
+        register_action(app, self.entry.id, self.entry.label, self.bindings.clone());
+
+        if let Some(apply) = self.apply {
+            apply(app);
+        }
+    }
+}
+
+/// Flip features whose action just fired
+fn toggle_from_actions(actions: Res<InputActions>, mut toggles: ResMut<FeatureToggles>) {
+    let pressed: Vec<&'static str> = toggles
+        .entries()
+        .iter()
+        .filter(|e| actions.just_triggered(e.id))
+        .map(|e| e.id)
+        .collect();
+
+    for id in pressed {
+        toggles.toggle(id);
+    }
+}
+
+/// Fade features towards their state, in real time so fades still finish
+/// while the scene is paused
+fn ramp_toggles(
+    time: Res<Time<Real>>,
+    ramp: Res<ToggleRamp>,
+    mut toggles: ResMut<FeatureToggles>,
+    mut settled: Local<bool>,
+) {
+    // Whatever saved settings or the url set at startup shouldn't fade in
+    if !*settled {
+        toggles.settle();
+        *settled = true;
+    } else if toggles.is_ramping() {
+        toggles.ramp(time.delta_secs(), &ramp);
+    }
+}
+
+/// Send FeatureToggled for anything that changed since last time
+fn send_toggled(
+    toggles: Res<FeatureToggles>,
+    mut last: Local<Vec<bool>>,
+    mut writer: MessageWriter<FeatureToggled>,
+) {
+    let current: Vec<bool> = toggles.entries().iter().map(|e| e.enabled).collect();
+
+    // Nothing to compare against on the first run, that's just startup state
+    if last.len() == current.len() {
+        for (entry, previous) in toggles.entries().iter().zip(last.iter()) {
+            if entry.enabled != *previous {
+                writer.write(FeatureToggled {
+                    id: entry.id,
+                    enabled: entry.enabled,
+                });
+            }
+        }
+    }
+
+    *last = current;
+}
+
+/// Log toggles mostly so the browser console shows what happened
+fn log_toggled(mut reader: MessageReader<FeatureToggled>) {
+    for toggled in reader.read() {
+        info!(
+            "{} {}",
+            toggled.id,
+            if toggled.enabled { "on" } else { "off" }
+        );
+    }
+}
+
+/// Insert or remove Enabled<T> on Target entities to match the toggle
+fn apply_feature<T: Send + Sync + 'static, Target: Component>(
+    toggles: Res<FeatureToggles>,
+    query: Query<(Entity, Has<Enabled<T>>), With<Target>>,
+    mut commands: Commands,
+) {
+    let should_enable = toggles.is_active::<T>();
+
+    for (entity, has_enabled) in query.iter() {
+        if should_enable && !has_enabled {
+            commands.entity(entity).insert(Enabled::<T>::default());
+        } else if !should_enable && has_enabled {
+            commands.entity(entity).remove::<Enabled<T>>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Foo;
+    struct Bar;
+
+    fn registry() -> FeatureToggles {
+        let mut toggles = FeatureToggles::default();
+        toggles.register(FeatureToggle::<Foo>::new("foo", "Foo", KeyCode::KeyF).entry);
+        toggles.register(
+            FeatureToggle::<Bar>::new("bar", "Bar", KeyCode::KeyB)
+                .off_by_default()
+                .entry,
+        );
+        toggles
+    }
+
+    #[test]
+    fn test_defaults() {
+        let toggles = registry();
+        assert!(toggles.is_enabled::<Foo>());
+        assert!(!toggles.is_enabled::<Bar>());
+        assert!(!toggles.is_enabled::<u32>());
+    }
+
+    #[test]
+    fn test_set_and_toggle_by_id() {
+        let mut toggles = registry();
+        assert!(toggles.set("bar", true));
+        assert!(toggles.is_enabled::<Bar>());
+        toggles.toggle("foo");
+        assert!(!toggles.is_enabled::<Foo>());
+        assert!(!toggles.set("nope", true));
+
+        toggles.reset();
+        assert!(toggles.is_enabled::<Foo>());
+        assert!(!toggles.is_enabled::<Bar>());
+    }
+
+    #[test]
+    fn test_ramp_fades_then_settles() {
+        let mut toggles = registry();
+        let ramp = ToggleRamp {
+            secs: 1.0,
+            easing: EaseFunction::Linear,
+        };
+
+        toggles.toggle("foo");
+        assert!(toggles.is_ramping());
+        toggles.ramp(0.25, &ramp);
+        assert_eq!(toggles.intensity::<Foo>(), 0.75);
+        // Still spinning down so Enabled<Foo> stays on for now
+        assert!(toggles.is_active::<Foo>());
+
+        toggles.ramp(1.0, &ramp);
+        assert_eq!(toggles.intensity::<Foo>(), 0.0);
+        assert!(!toggles.is_active::<Foo>());
+        assert!(!toggles.is_ramping());
+
+        toggles.set("bar", true);
+        toggles.settle();
+        assert_eq!(toggles.intensity::<Bar>(), 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_duplicate_id_panics() {
+        let mut toggles = registry();
+        toggles.register(FeatureToggle::<Bar>::new("foo", "Dupe", KeyCode::KeyD).entry);
+    }
+
+    #[test]
+    fn test_keyboard_and_messages() {
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .init_resource::<Touches>()
+            .init_resource::<Time<Real>>()
+            .add_plugins(FeatureToggle::<Foo>::new("foo", "Foo", KeyCode::KeyF));
+
+        // First update just records startup state
+        app.update();
+        assert!(
+            app.world()
+                .resource::<Messages<FeatureToggled>>()
+                .is_empty()
+        );
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::KeyF);
+        app.update();
+
+        assert!(!app.world().resource::<FeatureToggles>().is_enabled::<Foo>());
+        let messages = app.world().resource::<Messages<FeatureToggled>>();
+        let sent: Vec<_> = messages.iter_current_update_messages().collect();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].id, "foo");
+        assert!(!sent[0].enabled);
+    }
+}
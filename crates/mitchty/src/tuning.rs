@@ -0,0 +1,272 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::GOLDEN_ANGLE;
+use crate::camera::RotatingCamera;
+
+/// In the assets directory, read from disk natively or next to the page in
+/// the browser, release builds too, so the look can change without a rebuild.
+/// Without one the built in values stay.
+const TUNING_PATH: &str = "tuning.ron";
+
+/// Absolute rotation speed
+const SPEED: f32 = 2.25;
+/// How hard the cube speeds wander, in radians per second per square root
+/// second. Jitter scales with the square root of the frame time so the walk
+/// spreads out at the same rate whatever the refresh rate, this value matches
+/// the old fixed ±0.1 per frame at 60fps.
+const JITTER: f32 = 0.447_214;
+
+/// Every number that sets how the scene looks and moves
+#[derive(Resource, Reflect, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct SceneTuning {
+    pub cubes: CubeTuning,
+    pub colors: ColorTuning,
+    pub camera: CameraTuning,
+    pub lighting: LightingTuning,
+}
+
+#[derive(Reflect, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CubeTuning {
+    /// Scale of how far cube speeds swing either way in radians per second,
+    /// only the wave model goes by it for now. Kept inside min/max speed.
+    pub speed: f32,
+    /// Slowest/fastest any cube spins in radians per second, negative is the
+    /// other way round
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// Random walk wander, radians per second per square root second
+    pub jitter: f32,
+}
+
+impl Default for CubeTuning {
+    fn default() -> Self {
+        Self {
+            speed: SPEED,
+            min_speed: -SPEED,
+            max_speed: SPEED,
+            jitter: JITTER,
+        }
+    }
+}
+
+impl CubeTuning {
+    /// min_speed and max_speed, in order even if the sliders crossed
+    pub fn speed_bounds(&self) -> (f32, f32) {
+        (
+            self.min_speed.min(self.max_speed),
+            self.max_speed.max(self.min_speed),
+        )
+    }
+}
+
+#[derive(Reflect, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorTuning {
+    /// Degrees between neighbouring cubes in the golden angle palette
+    pub golden_angle: f32,
+    /// Degrees per second the hue animation cycles at
+    pub hue_speed: f32,
+}
+
+impl Default for ColorTuning {
+    fn default() -> Self {
+        Self {
+            golden_angle: GOLDEN_ANGLE,
+            hue_speed: 100.0,
+        }
+    }
+}
+
+#[derive(Reflect, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraTuning {
+    /// Auto rotation in radians per second
+    pub speed: f32,
+    pub radius: f32,
+    pub height: f32,
+    /// x, y, z the camera orbits and looks at
+    pub center: [f32; 3],
+}
+
+impl Default for CameraTuning {
+    /// Orbit through the original (3, 1, 3) camera position
+    fn default() -> Self {
+        Self {
+            speed: 0.3,
+            radius: Vec2::new(3.0, 3.0).length(),
+            height: 1.0,
+            center: [0.0, -0.5, 0.0],
+        }
+    }
+}
+
+impl CameraTuning {
+    /// Rotating camera at its starting angle
+    pub fn rotating_camera(&self) -> RotatingCamera {
+        let center = Vec3::from(self.center);
+        let offset = Vec2::from_angle(FRAC_PI_4) * self.radius;
+        let position = Vec3::new(center.x + offset.x, self.height, center.z + offset.y);
+        RotatingCamera::new(position, center, self.speed)
+    }
+}
+
+#[derive(Reflect, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LightingTuning {
+    pub env_map_intensity: f32,
+}
+
+impl Default for LightingTuning {
+    fn default() -> Self {
+        Self {
+            env_map_intensity: 2_000.0,
+        }
+    }
+}
+
+/// Tuning to ron for pasting into tuning.ron
+pub fn tuning_to_ron(tuning: &SceneTuning) -> ron::Result<String> {
+    ron::ser::to_string_pretty(tuning, ron::ser::PrettyConfig::default())
+}
+
+/// tuning.ron as loaded, copied into SceneTuning whenever it (re)loads
+#[derive(Asset, TypePath)]
+struct TuningFile(SceneTuning);
+
+/// No extensions, other ron files aren't tuning. Loading TUNING_PATH as a
+/// TuningFile picks this by asset type.
+#[derive(Default)]
+struct TuningLoader;
+
+impl AssetLoader for TuningLoader {
+    type Asset = TuningFile;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<TuningFile, BevyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(TuningFile(ron::de::from_bytes(&bytes)?))
+    }
+}
+
+/// Keeps tuning.ron loaded so it stays alive for reloads
+#[derive(Resource)]
+struct TuningHandle(#[allow(dead_code)] Handle<TuningFile>);
+
+/// Plugin for SceneTuning, loading it from tuning.ron if there is one and
+/// pushing camera changes onto the scene, lighting goes through the quality
+/// level first
+pub struct TuningPlugin;
+
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SceneTuning>()
+            .init_resource::<SceneTuning>()
+            .init_asset::<TuningFile>()
+            .init_asset_loader::<TuningLoader>()
+            .add_systems(Startup, load_tuning_file)
+            .add_systems(
+                Update,
+                (
+                    apply_tuning_file,
+                    apply_camera_tuning.run_if(resource_changed::<SceneTuning>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn load_tuning_file(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(TuningHandle(asset_server.load(TUNING_PATH)));
+}
+
+fn apply_tuning_file(
+    mut events: MessageReader<AssetEvent<TuningFile>>,
+    files: Res<Assets<TuningFile>>,
+    mut tuning: ResMut<SceneTuning>,
+) {
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && let Some(file) = files.get(*id)
+        {
+            info!("loaded scene tuning from {TUNING_PATH}");
+            tuning.set_if_neq(file.0.clone());
+        }
+    }
+}
+
+/// Only when the camera group itself changes, otherwise any tweak elsewhere
+/// would snap the camera back out of wherever it was zoomed to
+fn apply_camera_tuning(
+    tuning: Res<SceneTuning>,
+    mut last: Local<Option<CameraTuning>>,
+    mut cameras: Query<&mut RotatingCamera>,
+) {
+    if last.as_ref() == Some(&tuning.camera) {
+        return;
+    }
+    *last = Some(tuning.camera.clone());
+
+    for mut camera in cameras.iter_mut() {
+        camera.speed = tuning.camera.speed;
+        camera.radius = tuning.camera.radius;
+        camera.height = tuning.camera.height;
+        camera.center = tuning.camera.center.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_camera_matches_original() {
+        let camera = CameraTuning::default().rotating_camera();
+        let original =
+            RotatingCamera::new(Vec3::new(3.0, 1.0, 3.0), Vec3::new(0.0, -0.5, 0.0), 0.3);
+        assert!((camera.radius - original.radius).abs() < 1e-5);
+        assert!((camera.angle - original.angle).abs() < 1e-5);
+        assert_eq!(camera.height, original.height);
+        assert!(
+            camera
+                .transform()
+                .translation
+                .abs_diff_eq(Vec3::new(3.0, 1.0, 3.0), 1e-5)
+        );
+    }
+
+    #[test]
+    fn test_partial_ron_keeps_defaults() {
+        let tuning: SceneTuning = ron::from_str("(cubes: (jitter: 0.5))").unwrap();
+        assert_eq!(tuning.cubes.jitter, 0.5);
+        assert_eq!(tuning.cubes.max_speed, SPEED);
+        assert_eq!(tuning.camera, CameraTuning::default());
+
+        let text = tuning_to_ron(&tuning).unwrap();
+        assert_eq!(ron::from_str::<SceneTuning>(&text).unwrap(), tuning);
+    }
+
+    #[test]
+    fn test_speed_bounds_stay_ordered() {
+        let crossed = CubeTuning {
+            min_speed: 1.0,
+            max_speed: -1.0,
+            ..default()
+        };
+        assert_eq!(crossed.speed_bounds(), (-1.0, 1.0));
+    }
+}
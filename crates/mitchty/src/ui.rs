@@ -1,4 +1,23 @@
-use crate::{CameraRotation, CubeRotation, FpsDisplay, HueAnimation};
+use crate::camera::OrbitSettings;
+use crate::clock::{SPEEDS, TimeControl};
+use crate::crt::{CrtBlend, CrtParams, CrtPreset, CrtPresets, presets_from_ron, presets_to_ron};
+use crate::formation::{
+    EASINGS, Formation, FormationKind, FormationSlot, MorphSettings, easing_label,
+};
+use crate::input::{Binding, Gesture, InputActions, register_action};
+use crate::motion::MotionModel;
+use crate::pacing::{FrameLimit, Pacing, PresentChoice, SupportedPresentModes};
+use crate::palette::{GRADIENTS, HueSpace, Palette, PaletteMode};
+use crate::power::{PowerMode, PowerSettings};
+use crate::quality::{LEVELS, Quality};
+use crate::share::{base_url, encode_params};
+use crate::stats::{FrameTimes, frame_time_graph};
+use crate::toggle::{FeatureToggles, ToggleRamp};
+use crate::tuning::{
+    CameraTuning, ColorTuning, CubeTuning, LightingTuning, SceneTuning, tuning_to_ron,
+};
+use crate::{SceneSeed, TvSettingsResource, default_tv_settings};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
 
@@ -6,44 +25,77 @@ use bevy_egui::{EguiContexts, EguiPrimaryContextPass, egui};
 #[derive(Component)]
 pub struct ShowEgui;
 
-/// Marker component to indicate TV effect is enabled
-#[derive(Component)]
-pub struct TvEffectEnabled;
+/// Scratch state for the crt preset widgets
+#[derive(Default)]
+struct CrtPresetUi {
+    /// Name to save the current look under
+    name: String,
+    /// Ron text pasted in for import
+    import: String,
+    /// Last import/export result to show the user
+    status: String,
+}
+
+/// Rebinding state for the controls window
+#[derive(Default)]
+struct ControlsUi {
+    /// Action waiting on its next key/button press
+    capturing: Option<&'static str>,
+    /// Binding that clashed with another action, waiting on the user to confirm
+    conflict: Option<(&'static str, Binding)>,
+}
+
+/// What the cubes look like and how they're laid out, grouped to keep
+/// settings_ui under bevy's system parameter limit
+#[derive(SystemParam)]
+struct SceneLook<'w> {
+    formation: ResMut<'w, Formation>,
+    morph: ResMut<'w, MorphSettings>,
+    palette: ResMut<'w, Palette>,
+}
 
 /// Plugin for egui UI
 pub struct SettingsUiPlugin;
 
 impl Plugin for SettingsUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_egui)
-            .add_systems(Update, toggle_egui)
-            .add_systems(
-                EguiPrimaryContextPass,
-                settings_ui.run_if(any_with_component::<ShowEgui>),
-            );
+        register_action(
+            app,
+            "debug",
+            "Debug Window",
+            vec![
+                Binding::Key(KeyCode::KeyD),
+                Binding::Touch(Gesture::LongPress),
+                Binding::Gamepad(GamepadButton::Start),
+            ],
+        );
+
+        app.add_systems(Update, toggle_egui).add_systems(
+            EguiPrimaryContextPass,
+            (settings_ui, controls_ui, tuning_ui, performance_ui)
+                .run_if(any_with_component::<ShowEgui>),
+        );
     }
 }
 
-/// Spawn marker entities for egui state
-fn setup_egui(mut commands: Commands) {
-    // Don't spawn ShowEgui by default - egui starts hidden
-    commands.spawn(TvEffectEnabled);
-    commands.spawn(CameraRotation);
-    commands.spawn(CubeRotation);
-    commands.spawn(HueAnimation);
+/// Comma separated bindings of an action for labels
+pub fn binding_list(actions: &InputActions, id: &str) -> String {
+    actions
+        .bindings(id)
+        .iter()
+        .map(|b| b.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// System to control the egui settings/debug panel visibility
-/// d or touch (for things like ipad/wasm builds) toggles
+/// d or a long press (for things like ipad/wasm builds) toggles by default
 fn toggle_egui(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    touches: Res<Touches>,
+    actions: Res<InputActions>,
     egui_entity: Query<Entity, With<ShowEgui>>,
     mut commands: Commands,
 ) {
-    let should_toggle = keyboard.just_pressed(KeyCode::KeyD) || touches.any_just_pressed();
-
-    if should_toggle {
+    if actions.just_triggered("debug") {
         if let Ok(entity) = egui_entity.single() {
             commands.entity(entity).remove::<ShowEgui>();
         } else {
@@ -53,16 +105,35 @@ fn toggle_egui(
     }
 }
 
+/// Combo box picking one of the EASINGS curves
+fn easing_combo(ui: &mut egui::Ui, label: &str, easing: &mut EaseFunction) {
+    egui::ComboBox::from_label(label)
+        .selected_text(easing_label(*easing))
+        .show_ui(ui, |ui| {
+            for (name, curve) in EASINGS {
+                ui.selectable_value(easing, curve, name);
+            }
+        });
+}
+
 /// Display the settings UI using egui
 #[allow(clippy::too_many_arguments)]
 fn settings_ui(
     mut contexts: EguiContexts,
     mut clear_color: ResMut<ClearColor>,
-    tv_effect_query: Query<Entity, With<TvEffectEnabled>>,
-    fps_query: Query<Entity, With<FpsDisplay>>,
-    camera_rotation_query: Query<Entity, With<CameraRotation>>,
-    cube_rotation_query: Query<Entity, With<CubeRotation>>,
-    hue_animation_query: Query<Entity, With<HueAnimation>>,
+    mut tv_settings: ResMut<TvSettingsResource>,
+    mut presets: ResMut<CrtPresets>,
+    mut preset_ui: Local<CrtPresetUi>,
+    mut share_link: Local<String>,
+    seed: Res<SceneSeed>,
+    mut toggles: ResMut<FeatureToggles>,
+    mut ramp: ResMut<ToggleRamp>,
+    mut clock: ResMut<TimeControl>,
+    actions: Res<InputActions>,
+    mut orbit: ResMut<OrbitSettings>,
+    gamepads: Query<&Name, With<Gamepad>>,
+    mut models: Query<(&mut MotionModel, &FormationSlot)>,
+    mut look: SceneLook,
     mut commands: Commands,
 ) -> Result {
     egui::Window::new("Debug").show(contexts.ctx_mut()?, |ui| {
@@ -85,64 +156,720 @@ fn settings_ui(
         ui.separator();
         ui.heading("Effects");
 
-        // TV Effect checkbox
-        let mut tv_enabled = tv_effect_query.single().is_ok();
-        if ui.checkbox(&mut tv_enabled, "TV Effect").changed() {
-            if tv_enabled {
-                commands.spawn(TvEffectEnabled);
-            } else if let Ok(entity) = tv_effect_query.single() {
-                commands.entity(entity).despawn();
+        // One checkbox per registered feature toggle
+        let mut flipped = Vec::new();
+        for entry in toggles.entries() {
+            let mut enabled = entry.enabled;
+            let label = format!("{} ({})", entry.label, binding_list(&actions, entry.id));
+            if ui.checkbox(&mut enabled, label).changed() {
+                flipped.push((entry.id, enabled));
             }
         }
+        for (id, enabled) in flipped {
+            toggles.set(id, enabled);
+        }
 
-        // FPS Display checkbox
-        let mut fps_enabled = fps_query.single().is_ok();
-        if ui.checkbox(&mut fps_enabled, "FPS Display").changed() {
-            if fps_enabled {
-                commands.spawn(FpsDisplay);
-            } else if let Ok(entity) = fps_query.single() {
-                commands.entity(entity).despawn();
-            }
+        let mut new_ramp = ramp.clone();
+        ui.add(egui::Slider::new(&mut new_ramp.secs, 0.0..=3.0).text("Fade (s)"));
+        easing_combo(ui, "Fade Easing", &mut new_ramp.easing);
+
+        if ui.button("Reset Effects").clicked() {
+            toggles.reset();
+            new_ramp = ToggleRamp::default();
         }
+        ramp.set_if_neq(new_ramp);
 
-        // Camera Rotation checkbox
-        let mut camera_rotation_enabled = camera_rotation_query.single().is_ok();
+        ui.separator();
+        ui.heading("Time");
+
+        ui.horizontal(|ui| {
+            let label = if clock.paused { "Resume" } else { "Pause" };
+            if ui.button(label).clicked() {
+                clock.paused = !clock.paused;
+            }
+            if ui.button("Step").clicked() {
+                clock.step();
+            }
+        });
+        ui.horizontal(|ui| {
+            for speed in SPEEDS {
+                if ui
+                    .selectable_label(clock.speed == speed, format!("{speed}x"))
+                    .clicked()
+                {
+                    clock.speed = speed;
+                }
+            }
+        });
+        let mut speed = clock.speed;
         if ui
-            .checkbox(&mut camera_rotation_enabled, "Camera Rotation")
+            .add(
+                egui::Slider::new(&mut speed, SPEEDS[0]..=SPEEDS[SPEEDS.len() - 1])
+                    .logarithmic(true)
+                    .text("Speed"),
+            )
             .changed()
         {
-            if camera_rotation_enabled {
-                commands.spawn(CameraRotation);
-            } else if let Ok(entity) = camera_rotation_query.single() {
-                commands.entity(entity).despawn();
+            clock.speed = speed;
+        }
+
+        ui.separator();
+        ui.heading("Formation");
+
+        // Edit a copy so the cubes only get relaid out when something changes
+        let mut layout = look.formation.clone();
+        egui::ComboBox::from_label("Layout")
+            .selected_text(layout.kind.label())
+            .show_ui(ui, |ui| {
+                for kind in FormationKind::ALL {
+                    ui.selectable_value(&mut layout.kind, kind, kind.label());
+                }
+            });
+        if layout.kind == FormationKind::Grid {
+            ui.add(egui::Slider::new(&mut layout.grid.x, 1..=10).text("Grid X"));
+            ui.add(egui::Slider::new(&mut layout.grid.y, 1..=10).text("Grid Y"));
+            ui.add(egui::Slider::new(&mut layout.grid.z, 1..=10).text("Grid Z"));
+        } else {
+            ui.add(egui::Slider::new(&mut layout.count, 1..=500).text("Count"));
+        }
+        ui.add(egui::Slider::new(&mut layout.spacing, 0.5..=3.0).text("Spacing"));
+        if ui.button("Reset Formation").clicked() {
+            layout = Formation::default();
+        }
+        look.formation.set_if_neq(layout);
+
+        let mut morph = look.morph.clone();
+        ui.label("Transition");
+        ui.add(egui::Slider::new(&mut morph.duration, 0.0..=5.0).text("Duration (s)"));
+        ui.add(egui::Slider::new(&mut morph.stagger, 0.0..=5.0).text("Stagger (s)"));
+        easing_combo(ui, "Easing", &mut morph.easing);
+        if ui.button("Reset Transition").clicked() {
+            morph = MorphSettings::default();
+        }
+        look.morph.set_if_neq(morph);
+
+        ui.separator();
+        ui.heading("Palette");
+
+        // Any change repaints every cube so only write back real changes
+        let mut palette = look.palette.clone();
+        egui::ComboBox::from_label("Colors")
+            .selected_text(palette.mode.label())
+            .show_ui(ui, |ui| {
+                for mode in PaletteMode::ALL {
+                    ui.selectable_value(&mut palette.mode, mode, mode.label());
+                }
+            });
+        egui::ComboBox::from_label("Color Space")
+            .selected_text(palette.space.label())
+            .show_ui(ui, |ui| {
+                for space in HueSpace::ALL {
+                    ui.selectable_value(&mut palette.space, space, space.label());
+                }
+            });
+        match palette.mode {
+            PaletteMode::Fixed => {
+                let mut remove = None;
+                ui.horizontal_wrapped(|ui| {
+                    for (i, color) in palette.fixed.iter_mut().enumerate() {
+                        let mut rgb = [color.red, color.green, color.blue];
+                        if ui.color_edit_button_rgb(&mut rgb).changed() {
+                            *color = Srgba::rgb(rgb[0], rgb[1], rgb[2]);
+                        }
+                        if ui.small_button("x").clicked() {
+                            remove = Some(i);
+                        }
+                    }
+                    if ui.small_button("+").clicked() {
+                        let last = palette.fixed.last().copied().unwrap_or(Srgba::WHITE);
+                        palette.fixed.push(last);
+                    }
+                });
+                if let Some(i) = remove {
+                    palette.fixed.remove(i);
+                }
             }
+            PaletteMode::Image => {
+                egui::ComboBox::from_label("Gradient")
+                    .selected_text(
+                        GRADIENTS
+                            .get(palette.gradient)
+                            .map_or("", |(label, _)| *label),
+                    )
+                    .show_ui(ui, |ui| {
+                        for (i, (label, _)) in GRADIENTS.iter().enumerate() {
+                            ui.selectable_value(&mut palette.gradient, i, *label);
+                        }
+                    });
+            }
+            _ => {}
+        }
+        ui.add(egui::Slider::new(&mut palette.saturation, 0.0..=1.0).text("Saturation"));
+        ui.add(egui::Slider::new(&mut palette.lightness, 0.0..=1.0).text("Lightness"));
+        if ui.button("Reset Palette").clicked() {
+            palette = Palette::default();
         }
+        look.palette.set_if_neq(palette);
 
-        // Cube Rotation checkbox
-        let mut cube_rotation_enabled = cube_rotation_query.single().is_ok();
-        if ui
-            .checkbox(&mut cube_rotation_enabled, "Cube Rotation")
-            .changed()
-        {
-            if cube_rotation_enabled {
-                commands.spawn(CubeRotation);
-            } else if let Ok(entity) = cube_rotation_query.single() {
-                commands.entity(entity).despawn();
+        ui.separator();
+        ui.heading("Cube Motion");
+
+        // Everything on the same model shows that, otherwise mixed
+        let first = models.iter().next().map(|(model, _)| *model);
+        let shared = first.filter(|m| models.iter().all(|(other, _)| other == m));
+        let mut all = None;
+        egui::ComboBox::from_label("All Cubes")
+            .selected_text(shared.map_or("Mixed", MotionModel::label))
+            .show_ui(ui, |ui| {
+                for model in MotionModel::ALL {
+                    if ui
+                        .selectable_label(shared == Some(model), model.label())
+                        .clicked()
+                    {
+                        all = Some(model);
+                    }
+                }
+            });
+        if let Some(model) = all {
+            for (mut current, _) in models.iter_mut() {
+                current.set_if_neq(model);
+            }
+        }
+
+        ui.collapsing("Per Cube", |ui| {
+            // By formation slot, query order shuffles as cubes come and go
+            let mut cubes: Vec<_> = models.iter_mut().collect();
+            cubes.sort_by_key(|(_, slot)| slot.0);
+            for (mut current, slot) in cubes {
+                let mut selected = *current;
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt(("cube_motion", slot.0))
+                        .selected_text(selected.label())
+                        .show_ui(ui, |ui| {
+                            for model in MotionModel::ALL {
+                                ui.selectable_value(&mut selected, model, model.label());
+                            }
+                        });
+                    ui.label(format!("Cube {}", slot.0 + 1));
+                });
+                current.set_if_neq(selected);
             }
+        });
+
+        ui.separator();
+        ui.heading("Camera");
+
+        let mut settings = orbit.clone();
+        ui.add(
+            egui::Slider::new(&mut settings.idle_secs, 0.0..=30.0).text("Resume Rotation After"),
+        );
+        ui.add(egui::Slider::new(&mut settings.resume_secs, 0.0..=5.0).text("Resume Seconds"));
+        ui.add(egui::Slider::new(&mut settings.damping, 0.5..=20.0).text("Drag Damping"));
+        if ui.button("Reset Camera").clicked() {
+            settings = OrbitSettings::default();
+        }
+        orbit.set_if_neq(settings);
+
+        // Pads come and go, this just follows whatever is plugged in now
+        if gamepads.is_empty() {
+            ui.label("Gamepad: none connected");
+        }
+        for name in gamepads.iter() {
+            ui.label(format!("Gamepad: {name}"));
         }
 
-        // Hue Animation checkbox
-        let mut hue_animation_enabled = hue_animation_query.single().is_ok();
+        ui.separator();
+        ui.heading("CRT");
+
+        // Edit a copy so we only trip change detection when a slider moves
+        let mut settings = tv_settings.settings;
+        let mut changed = false;
+
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut settings.screen_shape_factor, 0.0..=1.0)
+                    .text("Screen Shape"),
+            )
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(&mut settings.rows, 1.0..=512.0).text("Rows"))
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(&mut settings.brightness, 0.0..=10.0).text("Brightness"))
+            .changed();
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut settings.edges_transition_size, 0.0..=0.5)
+                    .text("Edge Transition"),
+            )
+            .changed();
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut settings.channels_mask_min, 0.0..=1.0)
+                    .text("Channel Mask Min"),
+            )
+            .changed();
+
+        if ui.button("Reset CRT").clicked() {
+            settings = default_tv_settings();
+            changed = true;
+        }
+
+        // Manual edits win over any preset blend in flight
+        if changed {
+            tv_settings.settings = settings;
+            presets.active = None;
+            commands.remove_resource::<CrtBlend>();
+        }
+
+        ui.separator();
+        ui.heading("CRT Presets");
+
+        let mut selected: Option<CrtPreset> = None;
+        egui::ComboBox::from_label("Preset")
+            .selected_text(presets.active.as_deref().unwrap_or("custom"))
+            .show_ui(ui, |ui| {
+                for preset in presets.iter() {
+                    let is_active = presets.active.as_deref() == Some(preset.name.as_str());
+                    if ui.selectable_label(is_active, &preset.name).clicked() {
+                        selected = Some(preset.clone());
+                    }
+                }
+            });
+
+        if let Some(preset) = selected {
+            commands.insert_resource(CrtBlend::new(
+                tv_settings.settings.into(),
+                preset.params,
+                presets.blend_secs,
+            ));
+            presets.active = Some(preset.name);
+        }
+
+        let mut blend_secs = presets.blend_secs;
         if ui
-            .checkbox(&mut hue_animation_enabled, "Hue Animation")
+            .add(egui::Slider::new(&mut blend_secs, 0.0..=5.0).text("Blend Seconds"))
             .changed()
         {
-            if hue_animation_enabled {
-                commands.spawn(HueAnimation);
-            } else if let Ok(entity) = hue_animation_query.single() {
-                commands.entity(entity).despawn();
+            presets.blend_secs = blend_secs;
+        }
+
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut preset_ui.name);
+            if ui.button("Save Preset").clicked() && !preset_ui.name.trim().is_empty() {
+                let name = preset_ui.name.trim().to_string();
+                let params = CrtParams::from(tv_settings.settings);
+                let saved = presets.save_user(CrtPreset {
+                    name: name.clone(),
+                    params,
+                });
+                if saved != name {
+                    preset_ui.status = format!("{name} is built in, saved as {saved}");
+                }
+                presets.active = Some(saved);
             }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("Export Presets").clicked() {
+                match presets_to_ron(&presets.user) {
+                    Ok(text) => {
+                        ui.ctx().copy_text(text);
+                        preset_ui.status = "Copied user presets to clipboard".to_string();
+                    }
+                    Err(e) => preset_ui.status = format!("Export failed: {e}"),
+                }
+            }
+            if ui.button("Import Presets").clicked() {
+                match presets_from_ron(&preset_ui.import) {
+                    Ok(imported) => {
+                        preset_ui.status = format!("Imported {} presets", imported.len());
+                        for preset in imported {
+                            presets.save_user(preset);
+                        }
+                        preset_ui.import.clear();
+                    }
+                    Err(e) => preset_ui.status = format!("Import failed: {e}"),
+                }
+            }
+        });
+        ui.add(
+            egui::TextEdit::multiline(&mut preset_ui.import)
+                .hint_text("Paste exported presets here")
+                .desired_rows(3),
+        );
+        if !preset_ui.status.is_empty() {
+            ui.label(&preset_ui.status);
+        }
+
+        ui.separator();
+        ui.heading("Share");
+
+        ui.horizontal(|ui| {
+            ui.label(format!("Seed: {}", seed.0));
+            if ui.small_button("Copy").clicked() {
+                ui.ctx().copy_text(seed.0.to_string());
+            }
+        });
+
+        if ui.button("Copy share link").clicked() {
+            let params = encode_params(
+                &toggles,
+                seed.0,
+                clear_color.0,
+                presets.active.as_deref(),
+                &tv_settings.settings.into(),
+            );
+            *share_link = format!("{}?{}", base_url(), params);
+            ui.ctx().copy_text(share_link.clone());
+        }
+        if !share_link.is_empty() {
+            ui.add(egui::TextEdit::singleline(&mut *share_link).interactive(false));
+        }
+    });
+    Ok(())
+}
+
+/// Controls window for rebinding actions
+fn controls_ui(
+    mut contexts: EguiContexts,
+    mut actions: ResMut<InputActions>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut state: Local<ControlsUi>,
+) -> Result {
+    // Grab the next press for whatever is waiting on a binding, escape bails
+    if let Some(id) = state.capturing {
+        let pressed = keyboard
+            .get_just_pressed()
+            .next()
+            .map(|key| Binding::Key(*key))
+            .or_else(|| {
+                gamepads
+                    .iter()
+                    .find_map(|g| g.get_just_pressed().next().map(|b| Binding::Gamepad(*b)))
+            });
+
+        if keyboard.just_pressed(KeyCode::Escape) {
+            state.capturing = None;
+        } else if let Some(binding) = pressed {
+            state.capturing = None;
+            if actions.conflict(id, binding).is_some() {
+                state.conflict = Some((id, binding));
+            } else {
+                actions.bind(id, binding);
+            }
+        }
+    }
+
+    let capturing = state.capturing.is_some();
+    if actions.capturing != capturing {
+        actions.capturing = capturing;
+    }
+
+    egui::Window::new("Controls").show(contexts.ctx_mut()?, |ui| {
+        let mut unbind = None;
+        let mut bind = None;
+
+        egui::Grid::new("controls").striped(true).show(ui, |ui| {
+            for entry in actions.entries() {
+                ui.label(entry.label);
+                ui.horizontal(|ui| {
+                    for binding in &entry.bindings {
+                        if ui
+                            .button(binding.to_string())
+                            .on_hover_text("Click to remove")
+                            .clicked()
+                        {
+                            unbind = Some((entry.id, *binding));
+                        }
+                    }
+
+                    let add = if state.capturing == Some(entry.id) {
+                        "press a key/button..."
+                    } else {
+                        "+"
+                    };
+                    if ui.button(add).clicked() {
+                        state.capturing = Some(entry.id);
+                        state.conflict = None;
+                    }
+
+                    // Gestures can't be captured from a press so offer them directly
+                    ui.menu_button("touch", |ui| {
+                        for gesture in Gesture::ALL {
+                            let binding = Binding::Touch(gesture);
+                            if ui.button(binding.to_string()).clicked() {
+                                bind = Some((entry.id, binding));
+                                ui.close();
+                            }
+                        }
+                    });
+                });
+                ui.end_row();
+            }
+        });
+
+        if let Some((id, binding)) = bind {
+            if actions.conflict(id, binding).is_some() {
+                state.conflict = Some((id, binding));
+            } else {
+                actions.bind(id, binding);
+            }
+        }
+        if let Some((id, binding)) = unbind {
+            actions.unbind(id, binding);
+        }
+
+        if let Some((id, binding)) = state.conflict {
+            let other = actions
+                .conflict(id, binding)
+                .map(|e| e.label)
+                .unwrap_or("another action");
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                format!("{binding} is already bound to {other}"),
+            );
+            ui.horizontal(|ui| {
+                if ui.button("Rebind anyway").clicked() {
+                    actions.bind(id, binding);
+                    state.conflict = None;
+                }
+                if ui.button("Cancel").clicked() {
+                    state.conflict = None;
+                }
+            });
+        }
+
+        if ui.button("Reset Controls").clicked() {
+            actions.reset();
+            state.conflict = None;
         }
     });
     Ok(())
 }
+
+/// Scene tuning window, every look/motion number grouped with its own reset
+fn tuning_ui(
+    mut contexts: EguiContexts,
+    mut tuning: ResMut<SceneTuning>,
+    mut status: Local<String>,
+) -> Result {
+    // Edit a copy so the scene only sees real changes
+    let mut edited = tuning.clone();
+
+    egui::Window::new("Scene Tuning")
+        .default_open(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            ui.collapsing("Cubes", |ui| {
+                let cubes = &mut edited.cubes;
+                ui.add(egui::Slider::new(&mut cubes.speed, 0.0..=10.0).text("Speed Scale"))
+                    .on_hover_text("How far speeds swing, the wave model goes by it");
+                ui.add(egui::Slider::new(&mut cubes.min_speed, -10.0..=10.0).text("Min Speed"));
+                ui.add(egui::Slider::new(&mut cubes.max_speed, -10.0..=10.0).text("Max Speed"));
+                ui.add(egui::Slider::new(&mut cubes.jitter, 0.0..=5.0).text("Jitter"));
+                if ui.button("Reset Cubes").clicked() {
+                    *cubes = CubeTuning::default();
+                }
+            });
+
+            ui.collapsing("Colors", |ui| {
+                let colors = &mut edited.colors;
+                ui.add(
+                    egui::Slider::new(&mut colors.golden_angle, 0.0..=360.0).text("Golden Angle"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut colors.hue_speed, 0.0..=360.0).text("Hue Speed (°/s)"),
+                );
+                if ui.button("Reset Colors").clicked() {
+                    *colors = ColorTuning::default();
+                }
+            });
+
+            ui.collapsing("Camera", |ui| {
+                let camera = &mut edited.camera;
+                ui.add(egui::Slider::new(&mut camera.speed, -3.0..=3.0).text("Rotation Speed"));
+                ui.add(egui::Slider::new(&mut camera.radius, 1.5..=15.0).text("Radius"));
+                ui.add(egui::Slider::new(&mut camera.height, -3.0..=8.0).text("Height"));
+                ui.horizontal(|ui| {
+                    ui.label("Center");
+                    for axis in camera.center.iter_mut() {
+                        ui.add(egui::DragValue::new(axis).speed(0.05));
+                    }
+                });
+                if ui.button("Reset Camera").clicked() {
+                    *camera = CameraTuning::default();
+                }
+            });
+
+            ui.collapsing("Lighting", |ui| {
+                let lighting = &mut edited.lighting;
+                ui.add(
+                    egui::Slider::new(&mut lighting.env_map_intensity, 0.0..=10_000.0)
+                        .text("Environment Map"),
+                );
+                if ui.button("Reset Lighting").clicked() {
+                    *lighting = LightingTuning::default();
+                }
+            });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Reset All").clicked() {
+                    edited = SceneTuning::default();
+                }
+                // For pasting into tuning.ron
+                if ui.button("Copy as ron").clicked() {
+                    match tuning_to_ron(&edited) {
+                        Ok(text) => {
+                            ui.ctx().copy_text(text);
+                            *status = "Copied tuning to clipboard".to_string();
+                        }
+                        Err(e) => *status = format!("Copy failed: {e}"),
+                    }
+                }
+            });
+            if !status.is_empty() {
+                ui.label(&*status);
+            }
+        });
+
+    tuning.set_if_neq(edited);
+    Ok(())
+}
+
+/// Performance window, frame stats and everything trading looks for speed
+#[allow(clippy::too_many_arguments)]
+fn performance_ui(
+    mut contexts: EguiContexts,
+    frame_times: Res<FrameTimes>,
+    mut show_graph: Local<bool>,
+    mut quality: ResMut<Quality>,
+    mut power: ResMut<PowerSettings>,
+    power_mode: Res<PowerMode>,
+    mut pacing: ResMut<Pacing>,
+    supported: Res<SupportedPresentModes>,
+) -> Result {
+    egui::Window::new("Performance")
+        .default_open(false)
+        .show(contexts.ctx_mut()?, |ui| {
+            match frame_times.stats {
+                Some(stats) => {
+                    ui.label(format!(
+                        "{:.1} fps, {:.2} ms average",
+                        stats.fps, stats.frame_ms
+                    ));
+                    ui.label(format!(
+                        "1% low {:.1} fps, max {:.2} ms",
+                        stats.low_fps, stats.max_ms
+                    ));
+                }
+                None => {
+                    ui.label("Waiting on frames");
+                }
+            }
+            ui.checkbox(&mut show_graph, "Frame Time Graph");
+            if *show_graph {
+                frame_time_graph(ui, &frame_times.history);
+            }
+
+            ui.separator();
+
+            // Edit a copy, any change to Quality relays out and repaints the cubes
+            let mut new_quality = quality.clone();
+            let selected = if new_quality.auto {
+                format!("Auto ({})", new_quality.current().label)
+            } else {
+                new_quality.current().label.to_string()
+            };
+            egui::ComboBox::from_label("Quality")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut new_quality.auto, true, "Auto");
+                    for (level, entry) in LEVELS.iter().enumerate() {
+                        if ui
+                            .selectable_label(
+                                !new_quality.auto && new_quality.level == level,
+                                entry.label,
+                            )
+                            .clicked()
+                        {
+                            new_quality.auto = false;
+                            new_quality.level = level;
+                        }
+                    }
+                });
+            ui.add_enabled(
+                new_quality.auto,
+                egui::Slider::new(&mut new_quality.target_fps, 24.0..=144.0)
+                    .step_by(1.0)
+                    .text("Target FPS"),
+            );
+            let level = new_quality.current();
+            ui.label(format!(
+                "{:.0}% resolution, {}, tv {}, {:.0}% environment light, up to {} cubes",
+                level.render_scale * 100.0,
+                match (level.msaa, level.smaa) {
+                    (Msaa::Off, true) => "smaa".to_string(),
+                    (Msaa::Off, false) => "no antialiasing".to_string(),
+                    (msaa, _) => format!("{}x msaa", msaa.samples()),
+                },
+                if level.tv { "allowed" } else { "off" },
+                level.env_map * 100.0,
+                level.max_cubes
+            ));
+            quality.set_if_neq(new_quality);
+
+            ui.separator();
+
+            let mut new_power = power.clone();
+            ui.checkbox(
+                &mut new_power.enabled,
+                format!("Power Saving ({})", power_mode.label()),
+            );
+            ui.add_enabled_ui(new_power.enabled, |ui| {
+                ui.add(
+                    egui::Slider::new(&mut new_power.idle_after_secs, 5.0..=600.0)
+                        .logarithmic(true)
+                        .text("Idle after (s)"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut new_power.idle_fps, 1.0..=30.0)
+                        .step_by(1.0)
+                        .text("Idle FPS"),
+                );
+            });
+            power.set_if_neq(new_power);
+
+            let mut new_pacing = pacing.clone();
+            egui::ComboBox::from_label("Frame Limit")
+                .selected_text(new_pacing.limit.label())
+                .show_ui(ui, |ui| {
+                    for limit in FrameLimit::ALL {
+                        ui.selectable_value(&mut new_pacing.limit, limit, limit.label());
+                    }
+                });
+            let available = PresentChoice::available(&supported.lock());
+            // A saved pick this display can't do stays saved, it just runs Fifo
+            let selected = if available.contains(&new_pacing.present) {
+                new_pacing.present.label().to_string()
+            } else {
+                format!("{} (using Fifo)", new_pacing.present.label())
+            };
+            egui::ComboBox::from_label("Present Mode")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for present in available {
+                        ui.selectable_value(&mut new_pacing.present, present, present.label());
+                    }
+                })
+                .response
+                .on_hover_text(if cfg!(target_arch = "wasm32") {
+                    "Browsers always vsync, so only the vsync modes are here"
+                } else {
+                    "Mailbox and Immediate are only here when the display has them"
+                });
+            pacing.set_if_neq(new_pacing);
+        });
+    Ok(())
+}
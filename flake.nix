@@ -479,7 +479,10 @@
                 ''
               else
                 ''
-                  # nop
+                  echo "copying tuning.ron for release build, everything else is embedded"
+                  mkdir -p "$TMPDIR/assets"
+                  cp -v ${./crates/mitchty/src/assets/tuning.ron} "$TMPDIR/assets/tuning.ron"
+                  chmod u+w "$TMPDIR/assets/tuning.ron"
                 '';
           in
           pkgs.writeShellApplication {